@@ -0,0 +1,240 @@
+// A fluent way to make a `Crossword`, checking each field as it's set rather than
+// all at once in `CrosswordArgs::validate`. The grid comes first, since nearly
+// everything else is checked against it.
+
+use crate::{
+    CellMarkup, CluePlacement, ClueDetails, Crossword, CrosswordArgs, CrosswordCell, MultiError, PlayerState,
+    ValidationError,
+};
+
+/// Builds a `Crossword` a field at a time. Setters which can be checked on their own
+/// return an error straight away; `build` checks the whole puzzle once more.
+#[derive(Debug, Clone)]
+pub struct CrosswordBuilder {
+    args: CrosswordArgs,
+}
+
+impl CrosswordBuilder {
+    /// Start a puzzle from its grid, row by row.
+    pub fn new(width: u8, height: u8, grid: Vec<CrosswordCell>) -> Result<Self, ValidationError> {
+        if grid.len() != width as usize * height as usize {
+            return Err(ValidationError::InvalidGridSize { width, height, grid_len: grid.len() });
+        }
+        let args = CrosswordArgs { width, height, grid, ..Default::default() };
+        args.validate_rebuses()?;
+        Ok(CrosswordBuilder { args })
+    }
+
+    /// Bars change the numbering, so any clues already set are checked again.
+    pub fn markup(mut self, markup: Vec<CellMarkup>) -> Result<Self, ValidationError> {
+        self.args.markup = markup;
+        self.args.validate_markup()?;
+        self.check_clues()?;
+        Ok(self)
+    }
+
+    pub fn labels(mut self, labels: Vec<String>) -> Result<Self, ValidationError> {
+        self.args.labels = labels;
+        self.args.validate_labels()?;
+        Ok(self)
+    }
+
+    pub fn player(mut self, player: PlayerState) -> Result<Self, ValidationError> {
+        self.args.player = Some(player);
+        self.args.validate_player()?;
+        Ok(self)
+    }
+
+    pub fn across_clues(mut self, clues: Vec<(u16, String)>) -> Result<Self, ValidationError> {
+        self.args.across_clues = clues;
+        self.args.validate_clue_numbers().0?;
+        self.args.validate_clue_details(&self.args.across_clues, &self.args.across_details)?;
+        Ok(self)
+    }
+
+    pub fn down_clues(mut self, clues: Vec<(u16, String)>) -> Result<Self, ValidationError> {
+        self.args.down_clues = clues;
+        self.args.validate_clue_numbers().1?;
+        self.args.validate_clue_details(&self.args.down_clues, &self.args.down_details)?;
+        Ok(self)
+    }
+
+    /// One per across clue, so set after them.
+    pub fn across_details(mut self, details: Vec<ClueDetails>) -> Result<Self, ValidationError> {
+        self.args.across_details = details;
+        self.args.validate_clue_details(&self.args.across_clues, &self.args.across_details)?;
+        Ok(self)
+    }
+
+    /// One per down clue, so set after them.
+    pub fn down_details(mut self, details: Vec<ClueDetails>) -> Result<Self, ValidationError> {
+        self.args.down_details = details;
+        self.args.validate_clue_details(&self.args.down_clues, &self.args.down_details)?;
+        Ok(self)
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.args.title = title.into();
+        self
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.args.author = author.into();
+        self
+    }
+
+    pub fn copyright(mut self, copyright: impl Into<String>) -> Self {
+        self.args.copyright = copyright.into();
+        self
+    }
+
+    pub fn notes(mut self, notes: impl Into<String>) -> Self {
+        self.args.notes = notes.into();
+        self
+    }
+
+    pub fn date(mut self, date: impl Into<String>) -> Self {
+        self.args.date = date.into();
+        self
+    }
+
+    pub fn editor(mut self, editor: impl Into<String>) -> Self {
+        self.args.editor = editor.into();
+        self
+    }
+
+    pub fn publisher(mut self, publisher: impl Into<String>) -> Self {
+        self.args.publisher = publisher.into();
+        self
+    }
+
+    pub fn difficulty(mut self, difficulty: impl Into<String>) -> Self {
+        self.args.difficulty = difficulty.into();
+        self
+    }
+
+    pub fn intro(mut self, intro: impl Into<String>) -> Self {
+        self.args.intro = intro.into();
+        self
+    }
+
+    pub fn explanation(mut self, explanation: impl Into<String>) -> Self {
+        self.args.explanation = explanation.into();
+        self
+    }
+
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.args.url = url.into();
+        self
+    }
+
+    pub fn origin(mut self, origin: impl Into<String>) -> Self {
+        self.args.origin = origin.into();
+        self
+    }
+
+    pub fn clue_placement(mut self, clue_placement: CluePlacement) -> Self {
+        self.args.clue_placement = Some(clue_placement);
+        self
+    }
+
+    pub fn show_enumerations(mut self, show_enumerations: bool) -> Self {
+        self.args.show_enumerations = show_enumerations;
+        self
+    }
+
+    pub fn answers(mut self, answers: Vec<String>) -> Self {
+        self.args.answers = answers;
+        self
+    }
+
+    pub fn build(self) -> Result<Crossword, MultiError> {
+        self.args.validate()
+    }
+
+    /// The clues set so far. Those not yet set are left for `build`.
+    fn check_clues(&self) -> Result<(), ValidationError> {
+        let (across, down) = self.args.validate_clue_numbers();
+        if !self.args.across_clues.is_empty() {
+            across?;
+        }
+        if !self.args.down_clues.is_empty() {
+            down?;
+        }
+        Ok(())
+    }
+}
+
+/// Start from an existing puzzle, to change some of it.
+impl From<Crossword> for CrosswordBuilder {
+    fn from(xword: Crossword) -> Self {
+        CrosswordBuilder { args: xword.into() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(letters: &str) -> Vec<CrosswordCell> {
+        letters
+            .chars()
+            .map(|c| if c == '.' { CrosswordCell::Wall } else { CrosswordCell::Char(c) })
+            .collect()
+    }
+
+    fn clues(numbers: &[u16]) -> Vec<(u16, String)> {
+        numbers.iter().map(|&n| (n, format!("clue {n}"))).collect()
+    }
+
+    #[test]
+    fn test_build() {
+        let xword = CrosswordBuilder::new(2, 2, grid("ABCD"))
+            .unwrap()
+            .across_clues(clues(&[1, 3]))
+            .unwrap()
+            .down_clues(clues(&[1, 2]))
+            .unwrap()
+            .title("Tiny")
+            .author("Me")
+            .build()
+            .unwrap();
+        assert_eq!((xword.width(), xword.height()), (2, 2));
+        assert!(matches!(xword.cell(1, 0), Some(CrosswordCell::Char('C'))));
+        assert!(xword.cell(2, 0).is_none());
+        assert_eq!(xword.across_clues()[1], (3, "clue 3".to_string()));
+        assert_eq!(xword.title(), "Tiny");
+
+        // and back again, to edit.
+        let edited = CrosswordBuilder::from(xword).title("Tinier").build().unwrap();
+        assert_eq!(edited.title(), "Tinier");
+        assert_eq!(edited.author(), "Me");
+    }
+
+    #[test]
+    fn test_incremental_errors() {
+        assert!(matches!(
+            CrosswordBuilder::new(2, 2, grid("ABC")),
+            Err(ValidationError::InvalidGridSize { width: 2, height: 2, grid_len: 3 }),
+        ));
+        let builder = CrosswordBuilder::new(2, 2, grid("ABC.")).unwrap();
+        assert!(matches!(
+            builder.clone().across_clues(clues(&[1, 3])),
+            Err(ValidationError::MismatchedClueCount { expected: 1, actual: 2 }),
+        ));
+        assert!(matches!(
+            builder.clone().markup(vec![CellMarkup::default()]),
+            Err(ValidationError::InvalidMarkupSize { expected: 4, actual: 1 }),
+        ));
+
+        // a bar splits 1-Across, so the clues set earlier no longer fit.
+        let builder = builder.across_clues(clues(&[1])).unwrap();
+        let mut markup = vec![CellMarkup::default(); 4];
+        markup[0].bar_right = true;
+        assert!(matches!(builder.markup(markup), Err(ValidationError::MismatchedClueCount { .. })));
+
+        // clues not yet set are left for `build`.
+        let errors = CrosswordBuilder::new(2, 2, grid("ABC.")).unwrap().build().unwrap_err().into_error_map();
+        assert!(errors.contains_key("across_clues") && errors.contains_key("down_clues"));
+    }
+}
@@ -0,0 +1,393 @@
+// Exolve's text format, which is embedded in an HTML page alongside the Exolve script:
+//
+// exolve-begin
+//   exolve-id: title
+//   exolve-title: Title
+//   exolve-setter: Author
+//   exolve-copyright: Copyright
+//   exolve-width: 15
+//   exolve-height: 15
+//   exolve-grid:
+//     A B C . D E|F
+//     ...
+//   exolve-across:
+//     1 Clue (3)
+//   exolve-down:
+//     1 Clue (5)
+//   exolve-preamble:
+//     Notes
+// exolve-end
+//
+// In the grid, `.` is a block and `0` a blank cell. A cell may be followed by
+// decorators: `|` for a bar on its right, `_` for a bar below it, `+` for both,
+// `@` for a circle, and `!` for a cell that starts filled in.
+// `*` marks a diagramless cell, which we read as an ordinary one. `~` skips numbering
+// a cell, so the entries starting there are clued by their cells rather than a number:
+// we don't read those clues, so grids using `~` are refused.
+// Exolve has many more sections than these: we ignore the ones we don't know.
+
+use crate::multi_error::MultiError;
+use crate::{CellMarkup, Crossword, CrosswordArgs, CrosswordCell, ValidationError, position};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Characters with a meaning of their own in the grid, which can't be fill.
+const GRID_SPECIAL: &str = ".0?|_+@!*~";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ExolveParseError {
+    #[error("missing exolve-begin")]
+    MissingBegin,
+    #[error("missing exolve-{0} section")]
+    MissingSection(&'static str),
+    #[error("line {line}: invalid exolve-{section} {value:?}")]
+    InvalidValue {
+        line: usize,
+        section: &'static str,
+        value: String,
+    },
+    #[error("grid is height {height}, but found {actual} rows")]
+    InvalidHeight { height: usize, actual: usize },
+    #[error("line {line}: grid is width {width}, but row is length {actual}")]
+    InvalidWidth {
+        line: usize,
+        width: usize,
+        actual: usize,
+    },
+    #[error("line {line}: expected a clue, eg \"1 Clue (5)\"")]
+    InvalidClue { line: usize },
+    #[error("line {line}: unnumbered cells (`~`) are not supported")]
+    UnnumberedCell { line: usize },
+    #[error("{0}")]
+    Validation(#[from] ValidationError),
+}
+
+/// A section's value on its own line, and any lines after it, with their line numbers.
+struct Section<'a> {
+    line: usize,
+    value: &'a str,
+    lines: Vec<(usize, &'a str)>,
+}
+
+impl Crossword {
+    pub(crate) fn to_exolve(&self) -> Result<Vec<u8>, ValidationError> {
+        self.check_no_labels("Exolve")?;
+        let width = self.width as usize;
+        let mut grid = String::new();
+        for (idx, (cell, markup)) in self.grid.iter().zip(&self.markup).enumerate() {
+            if idx % width == 0 {
+                grid.push_str("    ");
+            }
+            let (row, col) = position(idx, width);
+            let unsupported = || ValidationError::UnsupportedCell { format: "Exolve", row, col };
+            match cell {
+                CrosswordCell::Wall => grid.push('.'),
+                CrosswordCell::Empty => grid.push('0'),
+                CrosswordCell::Char(c) if GRID_SPECIAL.contains(*c) || c.is_whitespace() => return Err(unsupported()),
+                CrosswordCell::Char(c) => grid.push(*c),
+                CrosswordCell::Rebus(_) => return Err(unsupported()),
+            }
+            match (markup.bar_right, markup.bar_bottom) {
+                (true, true) => grid.push('+'),
+                (true, false) => grid.push('|'),
+                (false, true) => grid.push('_'),
+                (false, false) => (),
+            }
+            if markup.circled {
+                grid.push('@');
+            }
+            if markup.given {
+                grid.push('!');
+            }
+            grid.push(if idx % width == width - 1 { '\n' } else { ' ' });
+        }
+
+        let mut out = String::from("exolve-begin\n");
+        let id: Vec<_> = self.title.split(|c: char| !c.is_alphanumeric()).filter(|s| !s.is_empty()).collect();
+        let id = if id.is_empty() { "crossword".to_string() } else { id.join("-").to_lowercase() };
+        writeln!(out, "  exolve-id: {id}").unwrap();
+        for (section, value) in [("title", &self.title), ("setter", &self.author), ("copyright", &self.copyright)] {
+            if !value.is_empty() {
+                writeln!(out, "  exolve-{section}: {}", value.replace('\n', " ")).unwrap();
+            }
+        }
+        writeln!(out, "  exolve-width: {}\n  exolve-height: {}", self.width, self.height).unwrap();
+        write!(out, "  exolve-grid:\n{grid}").unwrap();
+        for (section, clues) in [("across", &self.across_clues), ("down", &self.down_clues)] {
+            writeln!(out, "  exolve-{section}:").unwrap();
+            for (number, clue) in clues {
+                writeln!(out, "    {number} {}", clue.replace('\n', " ")).unwrap();
+            }
+        }
+        if !self.notes.is_empty() {
+            out.push_str("  exolve-preamble:\n");
+            for line in self.notes.lines() {
+                writeln!(out, "    {line}").unwrap();
+            }
+        }
+        out.push_str("exolve-end\n");
+        Ok(out.into_bytes())
+    }
+
+    /// Read a `Crossword` from Exolve's text format.
+    /// Anything outside of `exolve-begin` and `exolve-end`, like the rest of an HTML page, is ignored.
+    pub fn from_exolve(bytes: &[u8]) -> Result<Crossword, MultiError<ExolveParseError>> {
+        let text = String::from_utf8_lossy(bytes);
+        let sections = split_sections(&text).map_err(|err| MultiError::from([("exolve", err)]))?;
+        let section = |name: &'static str| {
+            sections
+                .get(name)
+                .ok_or_else(|| MultiError::from([(name, ExolveParseError::MissingSection(name))]))
+        };
+        let size = |name: &'static str| -> Result<u8, MultiError<ExolveParseError>> {
+            let Section { line, value, .. } = section(name)?;
+            value.parse().map_err(|_| {
+                let err = ExolveParseError::InvalidValue { line: *line, section: name, value: value.to_string() };
+                MultiError::from([(name, err)])
+            })
+        };
+        let width = size("width")?;
+        let height = size("height")?;
+        let (grid, markup) =
+            parse_grid(&section("grid")?.lines, width, height).map_err(|err| MultiError::from([("grid", err)]))?;
+
+        let mut issues = MultiError::new();
+        let mut clues = |name: &'static str, key: &'static str| {
+            let lines = sections.get(name).map_or(&[][..], |section| &section.lines[..]);
+            match parse_clues(lines) {
+                Ok(clues) => clues,
+                Err(err) => {
+                    issues.insert(key, err);
+                    Vec::new()
+                }
+            }
+        };
+        let across_clues = clues("across", "across_clues");
+        let down_clues = clues("down", "down_clues");
+        if !issues.is_empty() {
+            return Err(issues);
+        }
+
+        let value = |name| sections.get(name).map(|section| section.value.to_owned()).unwrap_or_default();
+        let notes = sections
+            .get("preamble")
+            .map(|section| {
+                let lines = section.lines.iter().map(|(_, line)| *line);
+                std::iter::once(section.value).filter(|v| !v.is_empty()).chain(lines).collect::<Vec<_>>().join("\n")
+            })
+            .unwrap_or_default();
+        let args = CrosswordArgs {
+            width,
+            height,
+            grid,
+            markup,
+            across_clues,
+            down_clues,
+            title: value("title"),
+            author: value("setter"),
+            copyright: value("copyright"),
+            notes,
+            ..Default::default()
+        };
+        args.validate().map_err(|errs| errs.map(ExolveParseError::from))
+    }
+}
+
+/// Map of section name, without the `exolve-`, to its contents.
+fn split_sections(text: &str) -> Result<HashMap<&str, Section<'_>>, ExolveParseError> {
+    let mut lines = text.lines().enumerate().map(|(idx, line)| (idx + 1, line.trim()));
+    lines.find(|(_, line)| *line == "exolve-begin").ok_or(ExolveParseError::MissingBegin)?;
+
+    let mut sections: HashMap<&str, Section> = HashMap::new();
+    let mut current = None;
+    for (idx, line) in lines {
+        if line == "exolve-end" {
+            break;
+        }
+        if let Some(rest) = line.strip_prefix("exolve-") {
+            let (name, value) = rest.split_once(':').unwrap_or((rest, ""));
+            let name = name.trim();
+            sections.insert(name, Section { line: idx, value: value.trim(), lines: Vec::new() });
+            current = Some(name);
+            continue;
+        }
+        if let Some(name) = current
+            && !line.is_empty()
+        {
+            sections.get_mut(name).unwrap().lines.push((idx, line));
+        }
+    }
+    Ok(sections)
+}
+
+fn parse_grid(
+    rows: &[(usize, &str)],
+    width: u8,
+    height: u8,
+) -> Result<(Vec<CrosswordCell>, Vec<CellMarkup>), ExolveParseError> {
+    let width = width as usize;
+    let height = height as usize;
+    if rows.len() != height {
+        return Err(ExolveParseError::InvalidHeight { height, actual: rows.len() });
+    }
+    let mut grid = Vec::new();
+    let mut markup: Vec<CellMarkup> = Vec::new();
+    for &(line, row) in rows {
+        let start = grid.len();
+        for c in row.chars().filter(|c| !c.is_whitespace()) {
+            let decorated = markup.len() > start;
+            match (c, markup.last_mut()) {
+                ('|', Some(m)) if decorated => m.bar_right = true,
+                ('_', Some(m)) if decorated => m.bar_bottom = true,
+                ('+', Some(m)) if decorated => (m.bar_right, m.bar_bottom) = (true, true),
+                ('@', Some(m)) if decorated => m.circled = true,
+                ('!', Some(m)) if decorated => m.given = true,
+                ('*', _) if decorated => (),
+                ('~', _) if decorated => return Err(ExolveParseError::UnnumberedCell { line }),
+                _ => {
+                    grid.push(match c {
+                        '.' => CrosswordCell::Wall,
+                        '0' | '?' => CrosswordCell::Empty,
+                        c => CrosswordCell::Char(c.to_uppercase().next().unwrap_or(c)),
+                    });
+                    markup.push(CellMarkup::default());
+                }
+            }
+        }
+        let actual = grid.len() - start;
+        if actual != width {
+            return Err(ExolveParseError::InvalidWidth { line, width, actual });
+        }
+    }
+    Ok((grid, markup))
+}
+
+/// Clues look like "12 Clue text (5)". Exolve allows extra detail after the number,
+/// like "12,14" for linked clues: we only keep the first number.
+fn parse_clues(lines: &[(usize, &str)]) -> Result<Vec<(u16, String)>, ExolveParseError> {
+    lines
+        .iter()
+        .map(|&(line, text)| {
+            let (label, clue) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+            let digits = label.find(|c: char| !c.is_ascii_digit()).unwrap_or(label.len());
+            let number = label[..digits].parse().map_err(|_| ExolveParseError::InvalidClue { line })?;
+            Ok((number, clue.trim().to_owned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let exolve = "\
+exolve-begin
+  exolve-width: 3
+  exolve-height: 2
+  exolve-grid:
+    A+@ B! 0
+    C D .
+  exolve-across:
+    1 First (2)
+    2 Second (2)
+  exolve-down:
+    1 Third (2)
+exolve-end
+";
+        let xword = Crossword::from_exolve(exolve.as_bytes()).unwrap();
+        assert!(matches!(xword.grid[2], CrosswordCell::Empty));
+        let exolve = xword.to_exolve().unwrap();
+        let round_trip = Crossword::from_exolve(&exolve).unwrap();
+        assert_eq!(round_trip.to_exolve().unwrap(), exolve);
+        assert!(matches!(round_trip.grid[2], CrosswordCell::Empty));
+        assert_eq!(round_trip.markup, xword.markup);
+        assert_eq!(round_trip.across_clues, xword.across_clues);
+
+        // fill which would read back as a decorator, and rebuses, have no way to be written.
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        assert!(matches!(xword.to_exolve(), Err(ValidationError::UnsupportedCell { format: "Exolve", row: 1, col: 1 })));
+        xword.grid = ['A', 'B', '?', 'D'].map(CrosswordCell::Char).to_vec();
+        assert!(matches!(xword.to_exolve(), Err(ValidationError::UnsupportedCell { format: "Exolve", row: 2, col: 1 })));
+    }
+
+    #[test]
+    fn test_parse() {
+        let exolve = "\
+<html><body><script>
+createExolve(`
+  exolve-begin
+    exolve-id: tiny
+    exolve-title: Tiny
+    exolve-setter: Me
+    exolve-width: 3
+    exolve-height: 3
+    exolve-grid:
+      A B_ C
+      D E|@F
+      G!H I*
+    exolve-across:
+      1 First (3)
+      3 Second (2)
+      5 Third (3)
+    exolve-down:
+      1 Fourth (3)
+      2,4 Fifth (2)
+      4 Sixth (2)
+    exolve-preamble: Notes
+      go here
+  exolve-end
+`);
+</script></body></html>";
+        let xword = Crossword::from_exolve(exolve.as_bytes()).unwrap();
+        assert_eq!(xword.title, "Tiny");
+        assert_eq!(xword.author, "Me");
+        assert!(xword.markup[1].bar_bottom);
+        assert!(xword.markup[4].bar_right && xword.markup[4].circled);
+        assert!(xword.markup[6].given);
+        assert!(matches!(xword.grid[8], CrosswordCell::Char('I')));
+        assert_eq!(xword.across_clues[1], (3, "Second (2)".to_string()));
+        assert_eq!(xword.down_clues[1], (2, "Fifth (2)".to_string()));
+        assert_eq!(xword.notes, "Notes\ngo here");
+
+        // the bars change the numbering, and where entries end.
+        let (across, down) = xword.grid().entry_cells();
+        assert_eq!(across, [(1, vec![0, 1, 2]), (3, vec![3, 4]), (5, vec![6, 7, 8])]);
+        assert_eq!(down, [(1, vec![0, 3, 6]), (2, vec![2, 5, 8]), (4, vec![4, 7])]);
+
+        // and the formats which can't represent bars refuse them.
+        assert!(matches!(xword.export(crate::FileFormat::Puz12), Err(ValidationError::UnsupportedBars { .. })));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let errors = Crossword::from_exolve(b"exolve-width: 3").unwrap_err().into_error_map();
+        assert!(matches!(errors["exolve"], ExolveParseError::MissingBegin));
+
+        let exolve = "exolve-begin\nexolve-width: 2\nexolve-height: two\n";
+        let errors = Crossword::from_exolve(exolve.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(&errors["height"], ExolveParseError::InvalidValue { line: 3, value, .. } if value == "two"));
+
+        let exolve = "exolve-begin\nexolve-width: 2\nexolve-height: 2\nexolve-grid:\nAB\nC D E\n";
+        let errors = Crossword::from_exolve(exolve.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(errors["grid"], ExolveParseError::InvalidWidth { line: 6, width: 2, actual: 3 }));
+
+        let exolve = "exolve-begin\nexolve-width: 2\nexolve-height: 2\nexolve-grid:\nAB\nC~ D\n";
+        let errors = Crossword::from_exolve(exolve.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(errors["grid"], ExolveParseError::UnnumberedCell { line: 6 }));
+
+        let exolve = "exolve-begin\nexolve-width: 2\nexolve-height: 2\nexolve-grid:\nAB\nCD\n\
+                      exolve-across:\n1 One\nthree Three\nexolve-down:\n1 One\n";
+        let errors = Crossword::from_exolve(exolve.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(errors["across_clues"], ExolveParseError::InvalidClue { line: 9 }));
+        assert!(!errors.contains_key("down_clues"));
+
+        let exolve = exolve.replace("three Three", "3 Three");
+        let errors = Crossword::from_exolve(exolve.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(
+            errors["down_clues"],
+            ExolveParseError::Validation(ValidationError::MismatchedClueCount { expected: 2, actual: 1 }),
+        ));
+    }
+}
@@ -0,0 +1,381 @@
+// A single standalone HTML page for solving the puzzle in a browser:
+// no network access, no dependencies, just inline CSS and JS.
+//
+// The numbering and the cells of each entry come from the same logic as every
+// other format, and are embedded as JSON for the script to read.
+
+use crate::xml::escape;
+use crate::{Crossword, CrosswordCell, Direction, Entry, NumberedCell};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const STYLE: &str = r#"
+:root { --cell: 36px; font-family: system-ui, sans-serif; }
+body { margin: 1.5em; color: #111; }
+h1 { margin: 0; font-size: 1.6em; }
+.byline { margin: 0.2em 0 1em; color: #555; }
+main { display: flex; flex-wrap: wrap; gap: 2em; align-items: flex-start; }
+#grid { display: grid; border: 2px solid #000; width: max-content; user-select: none; }
+.cell { position: relative; width: var(--cell); height: var(--cell); box-sizing: border-box;
+        border: 1px solid #888; background: #fff; cursor: pointer; }
+.cell.wall { background: #000; cursor: default; }
+.cell.shaded { background: #ddd; }
+.cell.circled::after { content: ""; position: absolute; inset: 1px; border: 1px solid #555;
+                       border-radius: 50%; pointer-events: none; }
+.cell.bar-right { border-right: 3px solid #000; }
+.cell.bar-bottom { border-bottom: 3px solid #000; }
+.cell.active { background: #cfe3ff; }
+.cell.cursor { background: #ffe066; }
+.num { position: absolute; top: 1px; left: 2px; font-size: 10px; line-height: 1; }
+.fill { position: absolute; inset: 0; display: flex; align-items: flex-end; justify-content: center;
+        padding-bottom: 3px; font-size: 20px; line-height: 1; overflow: hidden; text-transform: uppercase; }
+.fill.rebus { font-size: 10px; padding-bottom: 8px; }
+.wrong .fill { color: #c00; }
+.wrong::before { content: ""; position: absolute; inset: 0;
+                 background: linear-gradient(to top right, transparent 48%, #c00 49%, #c00 51%, transparent 52%); }
+.revealed .fill { color: #15c; }
+#current { min-height: 1.4em; margin: 0.5em 0; font-weight: bold; max-width: calc(var(--cell) * 15); }
+.controls button { margin: 0 0.3em 0.3em 0; }
+#status { font-weight: bold; color: #080; }
+.clues { display: flex; gap: 2em; }
+.clues h2 { font-size: 1.1em; margin: 0 0 0.3em; }
+.clues ol { list-style: none; margin: 0; padding: 0; width: 16em; max-height: 70vh; overflow-y: auto; }
+.clues li { padding: 3px 5px; cursor: pointer; }
+.clues li b { display: inline-block; min-width: 1.8em; }
+.clues li.current { background: #cfe3ff; }
+.clues li.crossing { background: #eef4ff; }
+footer { margin-top: 1.5em; color: #555; font-size: 0.9em; white-space: pre-wrap; }
+"#;
+
+const SCRIPT: &str = r#"
+(() => {
+  const P = JSON.parse(document.getElementById("puzzle-data").textContent);
+  const DIRS = ["across", "down"];
+  const gridEl = document.getElementById("grid");
+  const fills = P.cells.map(c => c && (c.entry || (c.given ? c.answer : "")));
+  const wrong = new Set();
+  const revealed = new Set(P.cells.flatMap((c, i) => c && c.given ? [i] : []));
+  const entryAt = { across: [], down: [] };
+  for (const d of DIRS) for (const e of P.entries[d]) for (const i of e.cells) entryAt[d][i] = e;
+  let cursor = P.cells.findIndex(c => c);
+  let dir = entryAt.across[cursor] ? "across" : "down";
+
+  const other = d => d === "across" ? "down" : "across";
+  const toggle = () => { if (entryAt[other(dir)][cursor]) dir = other(dir); };
+  const set = (i, value) => {
+    if (!P.cells[i] || revealed.has(i)) return;
+    fills[i] = value;
+    wrong.delete(i);
+  };
+  const advance = step => {
+    const entry = entryAt[dir][cursor];
+    if (!entry) return;
+    const next = entry.cells[entry.cells.indexOf(cursor) + step];
+    if (next !== undefined) cursor = next;
+  };
+  const move = key => {
+    const [dx, dy] = { ArrowLeft: [-1, 0], ArrowRight: [1, 0], ArrowUp: [0, -1], ArrowDown: [0, 1] }[key];
+    const want = dx ? "across" : "down";
+    if (want !== dir && entryAt[want][cursor]) { dir = want; return; }
+    let x = cursor % P.width + dx, y = Math.floor(cursor / P.width) + dy;
+    for (; x >= 0 && y >= 0 && x < P.width && y < P.height; x += dx, y += dy) {
+      const i = y * P.width + x;
+      if (P.cells[i]) { cursor = i; if (!entryAt[dir][i]) toggle(); return; }
+    }
+  };
+  const select = (d, entry) => {
+    dir = d;
+    cursor = entry.cells.find(i => !fills[i]) ?? entry.cells[0];
+  };
+  const nextEntry = step => {
+    const all = DIRS.flatMap(d => P.entries[d].map(e => [d, e]));
+    const idx = all.findIndex(([d, e]) => d === dir && e === entryAt[dir][cursor]);
+    const [d, e] = all[(idx + step + all.length) % all.length];
+    select(d, e);
+  };
+  const scope = which => {
+    if (which === "cell") return [cursor];
+    if (which === "word") return entryAt[dir][cursor]?.cells ?? [cursor];
+    return P.cells.flatMap((c, i) => c ? [i] : []);
+  };
+  const isRight = i => !P.cells[i].answer || (fills[i] || "").toUpperCase() === P.cells[i].answer.toUpperCase();
+
+  const cellEls = P.cells.map((c, i) => {
+    const el = document.createElement("div");
+    el.className = c ? "cell" : "cell wall";
+    if (c) {
+      if (c.circled) el.classList.add("circled");
+      if (c.shaded) el.classList.add("shaded");
+      if (c.barRight) el.classList.add("bar-right");
+      if (c.barBottom) el.classList.add("bar-bottom");
+      if (c.label) {
+        const num = document.createElement("span");
+        num.className = "num";
+        num.textContent = c.label;
+        el.append(num);
+      }
+      const fill = document.createElement("span");
+      fill.className = "fill";
+      el.append(fill);
+      el.addEventListener("click", () => {
+        if (cursor === i) toggle();
+        else { cursor = i; if (!entryAt[dir][i]) toggle(); }
+        render();
+      });
+    }
+    gridEl.append(el);
+    return el;
+  });
+  gridEl.style.gridTemplateColumns = `repeat(${P.width}, var(--cell))`;
+
+  const clueEls = { across: new Map(), down: new Map() };
+  for (const d of DIRS) {
+    const list = document.getElementById(d);
+    for (const e of P.entries[d]) {
+      const li = document.createElement("li");
+      const num = document.createElement("b");
+      num.textContent = e.label;
+      li.append(num, e.clue);
+      li.addEventListener("click", () => { select(d, e); render(); });
+      list.append(li);
+      clueEls[d].set(e, li);
+    }
+  }
+
+  function render() {
+    const entry = entryAt[dir][cursor];
+    cellEls.forEach((el, i) => {
+      if (!P.cells[i]) return;
+      const fill = el.lastChild;
+      fill.textContent = fills[i] || "";
+      fill.classList.toggle("rebus", (fills[i] || "").length > 1);
+      el.classList.toggle("cursor", i === cursor);
+      el.classList.toggle("active", !!entry && entry.cells.includes(i));
+      el.classList.toggle("wrong", wrong.has(i));
+      el.classList.toggle("revealed", revealed.has(i) && !P.cells[i].given);
+    });
+    for (const d of DIRS) {
+      for (const [e, li] of clueEls[d]) {
+        li.classList.toggle("current", d === dir && e === entry);
+        li.classList.toggle("crossing", d !== dir && e === entryAt[d][cursor]);
+      }
+    }
+    clueEls[dir].get(entry)?.scrollIntoView({ block: "nearest" });
+    document.getElementById("current").textContent =
+      entry ? `${entry.label}${dir === "across" ? "A" : "D"}: ${entry.clue}` : "";
+    const solved = P.hasSolution && P.cells.every((c, i) => !c || (fills[i] && isRight(i)));
+    document.getElementById("status").textContent = solved ? "Solved!" : "";
+  }
+
+  document.addEventListener("keydown", ev => {
+    if (ev.ctrlKey || ev.metaKey || ev.altKey) return;
+    const key = ev.key;
+    if (key.length === 1 && /\p{L}|\p{N}/u.test(key)) { set(cursor, key.toUpperCase()); advance(1); }
+    else if (key === "Backspace") {
+      if (!fills[cursor] || revealed.has(cursor)) advance(-1);
+      set(cursor, "");
+    }
+    else if (key === "Delete") set(cursor, "");
+    else if (key === " ") toggle();
+    else if (key === "Tab") nextEntry(ev.shiftKey ? -1 : 1);
+    else if (key.startsWith("Arrow")) move(key);
+    else if (key === "Escape" || key === "Insert") {
+      const value = prompt("Rebus", fills[cursor] || "");
+      if (value !== null) set(cursor, value.trim().toUpperCase());
+    }
+    else return;
+    ev.preventDefault();
+    render();
+  });
+
+  document.querySelector(".controls").addEventListener("click", ev => {
+    const button = ev.target.closest("button");
+    if (!button) return;
+    button.blur();
+    const [action, which] = button.dataset.action.split("-");
+    for (const i of scope(which)) {
+      if (action === "check" && fills[i] && !isRight(i)) wrong.add(i);
+      if (action === "reveal" && P.cells[i].answer) {
+        fills[i] = P.cells[i].answer;
+        revealed.add(i);
+        wrong.delete(i);
+      }
+    }
+    render();
+  });
+
+  render();
+})();
+"#;
+
+impl Crossword {
+    /// The puzzle, for the script: each cell, and each entry with its clue.
+    fn html_data(&self) -> Value {
+        let entries = self.player.as_ref().map(|player| &player.entries);
+        let cells: Vec<Value> = self
+            .grid
+            .iter()
+            .zip(&self.markup)
+            .zip(self.grid().iter_numbered().zip(self.cell_labels()))
+            .enumerate()
+            .map(|(idx, ((cell, markup), (numbered, label)))| {
+                let answer = match cell {
+                    CrosswordCell::Wall => return Value::Null,
+                    CrosswordCell::Empty => String::new(),
+                    CrosswordCell::Char(c) => c.to_string(),
+                    CrosswordCell::Rebus(s) => s.clone(),
+                };
+                let number = match numbered {
+                    NumberedCell::Numbered { number, .. } => Some(number),
+                    _ => None,
+                };
+                let entry = match entries.map(|entries| &entries[idx]) {
+                    Some(CrosswordCell::Char(c)) => c.to_string(),
+                    Some(CrosswordCell::Rebus(s)) => s.clone(),
+                    _ => String::new(),
+                };
+                json!({
+                    "answer": answer,
+                    "number": number,
+                    "label": label,
+                    "entry": entry,
+                    "circled": markup.circled,
+                    "shaded": markup.shaded,
+                    "given": markup.given,
+                    "barRight": markup.bar_right,
+                    "barBottom": markup.bar_bottom,
+                })
+            })
+            .collect();
+
+        let width = self.width as usize;
+        let all_entries = self.entries();
+        let entries = |direction: Direction, clues: &[(u16, String)]| -> Vec<Value> {
+            let clues: HashMap<_, _> = clues.iter().map(|(number, clue)| (*number, clue)).collect();
+            all_entries
+                .iter()
+                .filter(|entry| entry.direction == direction)
+                .map(|Entry { number, label, cells, .. }| {
+                    let cells: Vec<usize> = cells.iter().map(|&(row, col)| row * width + col).collect();
+                    json!({ "number": number, "label": label, "clue": clues[number], "cells": cells })
+                })
+                .collect()
+        };
+        json!({
+            "width": self.width,
+            "height": self.height,
+            "hasSolution": self.has_solution(),
+            "cells": cells,
+            "entries": {
+                "across": entries(Direction::Across, &self.across_clues),
+                "down": entries(Direction::Down, &self.down_clues),
+            },
+        })
+    }
+
+    pub(crate) fn to_html(&self) -> Vec<u8> {
+        // the clues mustn't be able to close the <script>: escape every '<' in the JSON.
+        let data = self.html_data().to_string().replace('<', "\\u003c");
+        let title = if self.title.is_empty() { "Crossword" } else { &self.title };
+
+        let mut out = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        out.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+        writeln!(out, "<title>{}</title>", escape(title)).unwrap();
+        writeln!(out, "<style>{STYLE}</style>\n</head>\n<body>").unwrap();
+        writeln!(out, "<h1>{}</h1>", escape(title)).unwrap();
+        let byline: Vec<_> = [&self.author, &self.copyright].into_iter().filter(|s| !s.is_empty()).collect();
+        if !byline.is_empty() {
+            let byline: Vec<_> = byline.into_iter().map(|s| escape(s)).collect();
+            writeln!(out, "<p class=\"byline\">{}</p>", byline.join(" &middot; ")).unwrap();
+        }
+        out.push_str("<main>\n<div>\n<div id=\"grid\"></div>\n<div id=\"current\"></div>\n<div class=\"controls\">\n");
+        // with no solution, there's nothing to check against.
+        if self.has_solution() {
+            out.push_str(concat!(
+                "<button data-action=\"check-cell\">Check letter</button>",
+                "<button data-action=\"check-word\">Check word</button>",
+                "<button data-action=\"check-puzzle\">Check puzzle</button><br>\n",
+                "<button data-action=\"reveal-cell\">Reveal letter</button>",
+                "<button data-action=\"reveal-word\">Reveal word</button>",
+                "<button data-action=\"reveal-puzzle\">Reveal puzzle</button>\n",
+            ));
+        }
+        out.push_str(concat!(
+            "<span id=\"status\"></span>\n</div>\n</div>\n",
+            "<div class=\"clues\">\n<div><h2>Across</h2><ol id=\"across\"></ol></div>\n",
+            "<div><h2>Down</h2><ol id=\"down\"></ol></div>\n</div>\n</main>\n",
+        ));
+        if !self.notes.is_empty() {
+            writeln!(out, "<footer>{}</footer>", escape(&self.notes)).unwrap();
+        }
+        writeln!(out, "<script type=\"application/json\" id=\"puzzle-data\">{data}</script>").unwrap();
+        writeln!(out, "<script>{SCRIPT}</script>\n</body>\n</html>").unwrap();
+        out.into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ClueDetails;
+
+    /// The JSON embedded in the page.
+    fn embedded_data(html: &str) -> Value {
+        let start = html.find("id=\"puzzle-data\">").unwrap() + "id=\"puzzle-data\">".len();
+        let end = start + html[start..].find("</script>").unwrap();
+        serde_json::from_str(&html[start..end]).unwrap()
+    }
+
+    #[test]
+    fn test_html() {
+        let xword = Crossword::from_puz(include_bytes!("test_files/encoding_oracle.puz")).unwrap();
+        let html = String::from_utf8(xword.to_html()).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("http://") && !html.contains("https://"));
+        assert!(html.contains(&format!("<title>{}</title>", escape(&xword.title))));
+
+        let data = embedded_data(&html);
+        let cells = data["cells"].as_array().unwrap();
+        assert_eq!(cells.len(), xword.grid.len());
+        assert_eq!(data["entries"]["across"].as_array().unwrap().len(), xword.across_clues.len());
+        let down = data["entries"]["down"].as_array().unwrap();
+        assert_eq!(down.len(), xword.down_clues.len());
+        assert_eq!(down[0]["clue"], xword.down_clues[0].1.as_str());
+        assert_eq!(down[0]["cells"].as_array().unwrap().len(), xword.height as usize);
+        assert_eq!(cells[0]["number"], 1);
+    }
+
+    #[test]
+    fn test_clue_cells() {
+        // 1-Across, read backwards.
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        xword.across_details = vec![ClueDetails { cells: vec![1, 0], ..Default::default() }, ClueDetails::default()];
+        let data = embedded_data(&String::from_utf8(xword.to_html()).unwrap());
+        assert_eq!(data["entries"]["across"][0]["cells"], json!([1, 0]));
+        assert_eq!(data["entries"]["across"][1]["cells"], json!([2, 3]));
+    }
+
+    #[test]
+    fn test_no_solution() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        assert!(String::from_utf8(xword.to_html()).unwrap().contains("data-action=\"check-puzzle\""));
+        xword.grid.fill(CrosswordCell::Empty);
+        let html = String::from_utf8(xword.to_html()).unwrap();
+        assert!(!html.contains("data-action=\"check") && !html.contains("data-action=\"reveal"));
+        assert_eq!(embedded_data(&html)["hasSolution"], false);
+    }
+
+    #[test]
+    fn test_script_injection() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        xword.title = "<script>alert(1)</script>".to_string();
+        xword.across_clues[0].1 = "</script><script>alert(1)</script>".to_string();
+        let html = String::from_utf8(xword.to_html()).unwrap();
+        // only our own two scripts.
+        assert_eq!(html.matches("<script").count(), 2);
+        assert_eq!(html.matches("</script>").count(), 2);
+        let data = embedded_data(&html);
+        assert_eq!(data["entries"]["across"][0]["clue"], xword.across_clues[0].1.as_str());
+    }
+}
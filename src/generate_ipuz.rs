@@ -13,24 +13,90 @@
 use crate::lit_str;
 use crate::multi_error::MultiError;
 use crate::validation::{ClueError, validate_clues};
-use crate::{Crossword, CrosswordCell, Grid, NumberedCell};
+use crate::{
+    CellMarkup, ClueDetails, CluePlacement, ClueReference, Crossword, CrosswordArgs, CrosswordCell, Direction, Grid,
+    NumberedCell, ValidationError, labelled_numbers, position,
+};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt;
 use std::iter::zip;
 
-lit_str!(Version, "http://ipuz.org/v1");
 lit_str!(Kind, "http://ipuz.org/crossword#1");
 
-type ClueList = Vec<(u16, String)>;
+/// Which version of the ipuz spec to write. Either is read.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum IPuzVersion {
+    #[default]
+    #[serde(rename = "http://ipuz.org/v1")]
+    V1,
+    /// Adds clue explanations, `clueplacement`, `showenumerations`, and `answer`/`answers`.
+    #[serde(rename = "http://ipuz.org/v2")]
+    V2,
+}
+
+/// Clues keyed by the label of the cell their entry starts at.
+type ClueList = Vec<Clue>;
+
+/// Either `[label, "clue"]`, or an object with more to say.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum Clue {
+    Pair(StringOrNum, String),
+    Object(ClueObject),
+}
+
+// Only the parts of the spec we can represent, again.
+// `number` may be left out for `label`, the label of the cell the entry starts at.
+// `numbers` is a clue spanning several entries, which we refuse.
+#[derive(Deserialize, Serialize)]
+struct ClueObject {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    number: Option<StringOrNum>,
+    #[serde(default, skip_serializing)]
+    numbers: Vec<StringOrNum>,
+    #[serde(default, skip_serializing)]
+    label: Option<StringOrNum>,
+    #[serde(default)]
+    clue: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    enumeration: Option<StringOrNum>,
+    /// `[column, row]`, counting from 1.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cells: Vec<[usize; 2]>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    references: Vec<CrossReference>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    hints: Vec<String>,
+    /// v2 only.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    explanation: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct CrossReference {
+    direction: String,
+    number: StringOrNum,
+}
+
+/// ipuz directions may carry a label of their own, eg "Across:Rows".
+fn direction_from_ipuz(direction: &str) -> Option<Direction> {
+    let name = direction.split(':').next().unwrap_or(direction).trim();
+    if name.eq_ignore_ascii_case("across") {
+        Some(Direction::Across)
+    } else if name.eq_ignore_ascii_case("down") {
+        Some(Direction::Down)
+    } else {
+        None
+    }
+}
 
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
-struct Clues<'a> {
-    #[serde(borrow)]
-    across: Cow<'a, ClueList>,
-    #[serde(borrow)]
-    down: Cow<'a, ClueList>,
+struct Clues {
+    across: ClueList,
+    down: ClueList,
 }
 
 #[derive(Deserialize, Serialize, Clone, Copy)]
@@ -40,7 +106,9 @@ struct Dimensions {
 }
 
 #[derive(thiserror::Error, Debug)]
-enum DeserializeError {
+pub enum DeserializeError {
+    #[error("invalid ipuz: {0}")]
+    Json(#[from] serde_json::Error),
     #[error("expected {expected} clues, found {actual}")]
     MismatchedClueCount { expected: usize, actual: usize },
     #[error("found misordered clues. Clue numbers must be strictly increasing")]
@@ -58,7 +126,7 @@ enum DeserializeError {
         actual: usize,
     },
     #[error(
-        "invalid solution item at {row},{col}: expected string or block ({block}), but found {actual}"
+        "invalid solution item at row {row}, column {col}: expected string or block ({block}), but found {actual}"
     )]
     InvalidSolutionItem {
         row: usize,
@@ -66,19 +134,42 @@ enum DeserializeError {
         block: StringOrNum,
         actual: StringOrNum,
     },
-    #[error("invalid numbering at {row},{col}: expected {expected} but found {actual}")]
+    #[error("invalid numbering at row {row}, column {col}: expected {expected} but found {actual}")]
     InvalidNumbering {
         row: usize,
         col: usize,
         expected: LabeledCellValue,
         actual: LabeledCellValue,
     },
-    #[error("error in labeled cell at {row},{col}: {error}")]
+    #[error("clue refers to unknown label {0:?}")]
+    UnknownLabel(String),
+    #[error("clue refers to label {0:?}, which more than one entry in that direction starts at")]
+    AmbiguousLabel(String),
+    #[error("clue refers to unknown direction {0:?}")]
+    UnknownDirection(String),
+    #[error("clue has neither a number nor a label")]
+    MissingClueNumber,
+    #[error("clue spans several entries ({0}), which isn't supported")]
+    MultiEntryClue(String),
+    #[error("clue refers to the cell at row {row}, column {col}, which is outside the grid")]
+    ClueCellOutOfBounds { row: usize, col: usize },
+    #[error("error in labeled cell at row {row}, column {col}: {error}")]
     LabeledCellError {
         row: usize,
         col: usize,
         error: LabeledCellError,
     },
+    #[error("{0}")]
+    Validation(#[from] ValidationError),
+}
+
+impl Serialize for DeserializeError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
 }
 
 impl From<ClueError> for DeserializeError {
@@ -106,7 +197,7 @@ fn validate_dimensions<T>(dim: Dimensions, puzzle: &[Vec<T>]) -> Result<(), Dese
     }
     let err = puzzle.iter().enumerate().find_map(|(row, r)| {
         (r.len() != width).then_some(DeserializeError::InvalidWidth {
-            row,
+            row: row + 1,
             width,
             actual: r.len(),
         })
@@ -123,20 +214,53 @@ fn validate_dimensions<T>(dim: Dimensions, puzzle: &[Vec<T>]) -> Result<(), Dese
 /// for manipulation/processing. Used as the serde layer.
 #[derive(Deserialize, Serialize)]
 struct IPuzRaw<'a> {
-    version: Version,
+    version: IPuzVersion,
     kind: [Kind; 1],
-    title: &'a str,
-    copyright: &'a str,
-    author: &'a str,
-    notes: &'a str,
+    #[serde(borrow, default)]
+    title: Cow<'a, str>,
+    #[serde(borrow, default)]
+    copyright: Cow<'a, str>,
+    #[serde(borrow, default)]
+    author: Cow<'a, str>,
+    #[serde(borrow, default)]
+    notes: Cow<'a, str>,
+    #[serde(borrow, default, skip_serializing_if = "str::is_empty")]
+    date: Cow<'a, str>,
+    #[serde(borrow, default, skip_serializing_if = "str::is_empty")]
+    editor: Cow<'a, str>,
+    #[serde(borrow, default, skip_serializing_if = "str::is_empty")]
+    publisher: Cow<'a, str>,
+    #[serde(borrow, default, skip_serializing_if = "str::is_empty")]
+    difficulty: Cow<'a, str>,
+    #[serde(borrow, default, skip_serializing_if = "str::is_empty")]
+    intro: Cow<'a, str>,
+    #[serde(borrow, default, skip_serializing_if = "str::is_empty")]
+    explanation: Cow<'a, str>,
+    #[serde(borrow, default, skip_serializing_if = "str::is_empty")]
+    url: Cow<'a, str>,
+    #[serde(borrow, default, skip_serializing_if = "str::is_empty")]
+    origin: Cow<'a, str>,
     dimensions: Dimensions,
     #[serde(default = "default_block")]
     block: StringOrNum,
     #[serde(default = "default_empty")]
     empty: StringOrNum,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    styles: HashMap<String, StyleSpec>,
     puzzle: Vec<Vec<LabeledCell>>,
-    solution: Vec<Vec<CrosswordValue>>,
-    clues: Clues<'a>,
+    /// Left out of a puzzle whose solution is withheld.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    solution: Option<Vec<Vec<CrosswordValue>>>,
+    clues: Clues,
+    // The rest are v2 only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    clueplacement: Option<CluePlacement>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    showenumerations: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    answer: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    answers: Vec<String>,
 }
 
 fn default_block() -> StringOrNum {
@@ -149,7 +273,7 @@ fn default_empty() -> StringOrNum {
 
 #[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
 #[serde(untagged)]
-enum StringOrNum {
+pub enum StringOrNum {
     String(String),
     Num(i32),
 }
@@ -164,26 +288,39 @@ impl fmt::Display for StringOrNum {
 }
 
 #[derive(Debug, thiserror::Error)]
-enum LabeledCellError {
-    #[error("string labels are unsupported (found {0:?})")]
-    String(String),
+pub enum LabeledCellError {
     #[error("numeric label is out of supported range (found {0:?})")]
     Num(i32),
 }
 
 // nb this does not cover the possible values of a LabeledCell per the spec,
 // but the spec is too open-ended and I do not care right now.
+// Styles are read as far as `CellMarkup` can represent them.
 #[derive(Deserialize, Serialize, Clone)]
 #[serde(untagged)]
 enum LabeledCell {
     Raw(StringOrNum),
-    Cell { cell: StringOrNum },
+    Cell {
+        cell: StringOrNum,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        style: Option<Style>,
+        /// A given letter, filled in for the solver.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<String>,
+    },
 }
 
 impl LabeledCell {
-    fn cell(num: i32) -> Self {
+    fn new(cell: StringOrNum, fill: &CrosswordCell, markup: &CellMarkup) -> Self {
+        let value = match fill {
+            CrosswordCell::Char(c) if markup.given => Some(c.to_string()),
+            CrosswordCell::Rebus(s) if markup.given => Some(s.to_string()),
+            _ => None,
+        };
         LabeledCell::Cell {
-            cell: StringOrNum::Num(num),
+            cell,
+            style: StyleSpec::from_markup(markup).map(Style::Spec),
+            value,
         }
     }
 
@@ -196,20 +333,106 @@ impl LabeledCell {
         match sorn {
             sorn if sorn == block => Ok(LabeledCellValue::Block),
             sorn if sorn == empty => Ok(LabeledCellValue::Empty),
-            StringOrNum::String(string) => Err(LabeledCellError::String(string.to_owned())),
+            StringOrNum::String(string) => Ok(LabeledCellValue::Label(string.to_owned())),
             &StringOrNum::Num(num) => {
                 let num: u16 = num.try_into().map_err(|_| LabeledCellError::Num(num))?;
                 Ok(LabeledCellValue::Number(num))
             }
         }
     }
+
+    /// The cell's style, looking up named styles in the puzzle's `styles`.
+    fn style<'s>(&'s self, styles: &'s HashMap<String, StyleSpec>) -> Option<&'s StyleSpec> {
+        match self {
+            LabeledCell::Cell { style: Some(Style::Spec(spec)), .. } => Some(spec),
+            LabeledCell::Cell { style: Some(Style::Named(name)), .. } => styles.get(name),
+            _ => None,
+        }
+    }
+
+    /// Markup for this cell, except for bars: a bar on the top or left edge
+    /// belongs to a neighbouring cell.
+    fn markup(&self, styles: &HashMap<String, StyleSpec>) -> CellMarkup {
+        let given = match self {
+            LabeledCell::Cell { value, .. } => value.as_ref().is_some_and(|value| !value.is_empty()),
+            LabeledCell::Raw(_) => false,
+        };
+        let Some(spec) = self.style(styles) else {
+            return CellMarkup { given, ..Default::default() };
+        };
+        CellMarkup {
+            circled: spec.shapebg.as_deref() == Some("circle"),
+            shaded: spec.highlight,
+            given,
+            color: spec.color.as_ref().and_then(color_from_ipuz),
+            text_color: spec.colortext.as_ref().and_then(color_from_ipuz),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+enum Style {
+    Named(String),
+    Spec(StyleSpec),
+}
+
+// Again, only the parts of the spec we can represent. Anything else is ignored.
+#[derive(Deserialize, Serialize, Clone)]
+struct StyleSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shapebg: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    highlight: bool,
+    /// Either a hex color, or a number referring to a color the application picks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<StringOrNum>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    colortext: Option<StringOrNum>,
+    /// Edges of the cell with a bar: any of "T", "R", "B", "L".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    barred: Option<String>,
+}
+
+impl StyleSpec {
+    fn from_markup(markup: &CellMarkup) -> Option<Self> {
+        let barred: String = [(markup.bar_right, 'R'), (markup.bar_bottom, 'B')]
+            .into_iter()
+            .filter_map(|(barred, edge)| barred.then_some(edge))
+            .collect();
+        let color_to_ipuz = |color: &String| StringOrNum::String(color.trim_start_matches('#').to_owned());
+        let spec = StyleSpec {
+            shapebg: markup.circled.then(|| "circle".to_string()),
+            highlight: markup.shaded,
+            color: markup.color.as_ref().map(color_to_ipuz),
+            colortext: markup.text_color.as_ref().map(color_to_ipuz),
+            barred: (!barred.is_empty()).then_some(barred),
+        };
+        let is_empty = spec.shapebg.is_none()
+            && !spec.highlight
+            && spec.color.is_none()
+            && spec.colortext.is_none()
+            && spec.barred.is_none();
+        (!is_empty).then_some(spec)
+    }
+}
+
+/// ipuz colors are hex without the leading `#`. Numbered colors are up to the
+/// application to pick, so we drop them.
+fn color_from_ipuz(color: &StringOrNum) -> Option<String> {
+    let StringOrNum::String(color) = color else {
+        return None;
+    };
+    let hex = color.trim_start_matches('#');
+    (!hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit())).then(|| format!("#{hex}"))
 }
 
 impl From<LabeledCell> for StringOrNum {
     fn from(cell: LabeledCell) -> StringOrNum {
         match cell {
             LabeledCell::Raw(sorn) => sorn,
-            LabeledCell::Cell { cell } => cell,
+            LabeledCell::Cell { cell, .. } => cell,
         }
     }
 }
@@ -218,16 +441,32 @@ impl<'a> From<&'a LabeledCell> for &'a StringOrNum {
     fn from(cell: &'a LabeledCell) -> &'a StringOrNum {
         match cell {
             LabeledCell::Raw(sorn) => sorn,
-            LabeledCell::Cell { cell } => cell,
+            LabeledCell::Cell { cell, .. } => cell,
         }
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
-enum LabeledCellValue {
+pub enum LabeledCellValue {
     Block,
     Empty,
     Number(u16),
+    Label(String),
+}
+
+impl LabeledCellValue {
+    fn is_block(&self) -> bool {
+        matches!(self, LabeledCellValue::Block)
+    }
+
+    /// The label as text, empty for no label.
+    fn label(&self) -> String {
+        match self {
+            LabeledCellValue::Block | LabeledCellValue::Empty => String::new(),
+            LabeledCellValue::Number(number) => number.to_string(),
+            LabeledCellValue::Label(label) => label.clone(),
+        }
+    }
 }
 
 impl From<NumberedCell> for LabeledCellValue {
@@ -246,44 +485,113 @@ impl fmt::Display for LabeledCellValue {
             LabeledCellValue::Block => write!(f, "block"),
             LabeledCellValue::Empty => write!(f, "no label"),
             LabeledCellValue::Number(number) => write!(f, "#{number}"),
+            LabeledCellValue::Label(label) => write!(f, "{label:?}"),
         }
     }
 }
 
 type CrosswordValue = StringOrNum;
 
-impl<'a> From<&'a Crossword> for IPuzRaw<'a> {
-    fn from(xword: &'a Crossword) -> Self {
+/// Numeric labels are written as numbers, like ordinary ipuz numbering.
+fn label_to_ipuz(label: &str) -> StringOrNum {
+    match label.parse() {
+        Ok(num) => StringOrNum::Num(num),
+        Err(_) => StringOrNum::String(label.to_owned()),
+    }
+}
+
+/// A label as it's shown, whether the json has it as a number or a string.
+fn label_text(label: StringOrNum) -> String {
+    match label {
+        StringOrNum::Num(num) => num.to_string(),
+        StringOrNum::String(label) => label,
+    }
+}
+
+impl<'a> IPuzRaw<'a> {
+    fn new(xword: &'a Crossword, version: IPuzVersion) -> Self {
         let Crossword {
             width,
             height,
             grid,
+            markup,
+            labels: _,
+            player: _,
             across_clues,
             down_clues,
+            across_details,
+            down_details,
             title,
             author,
             copyright,
             notes,
+            date,
+            editor,
+            publisher,
+            difficulty,
+            intro,
+            explanation,
+            url,
+            origin,
+            clue_placement,
+            show_enumerations,
+            answers,
         } = xword;
+        let v2 = version == IPuzVersion::V2;
 
         let chunk = *width as usize;
 
         let empty = default_empty();
-        let empty_cell = LabeledCell::Cell {
-            cell: empty.clone(),
-        };
         let block = default_block();
         let block_cell = LabeledCell::Raw(block.clone());
-        let puzzle: Vec<_> = xword
-            .grid()
-            .iter_numbered()
-            .map(|cell| match cell {
-                NumberedCell::Wall => block_cell.clone(),
-                NumberedCell::Empty => empty_cell.clone(),
-                NumberedCell::Numbered { number, .. } => LabeledCell::cell(number.into()),
+        let puzzle: Vec<_> = zip(xword.cell_labels(), zip(grid, markup))
+            .map(|(label, (fill, markup))| {
+                let cell = match fill {
+                    CrosswordCell::Wall => return block_cell.clone(),
+                    _ if label.is_empty() => empty.clone(),
+                    _ => label_to_ipuz(&label),
+                };
+                LabeledCell::new(cell, fill, markup)
             })
             .collect();
         let puzzle = puzzle.chunks(chunk).map(|c| c.to_vec()).collect();
+        let clue_labels = xword.clue_labels();
+        // clues and references are validated against the grid, so each has a numbered cell.
+        let label = |number: &u16| label_to_ipuz(&clue_labels[number]);
+        let clue_list = |clues: &[(u16, String)], details: &[ClueDetails]| -> ClueList {
+            clues
+                .iter()
+                .enumerate()
+                .map(|(idx, (number, clue))| {
+                    let mut details = details.get(idx).cloned().unwrap_or_default();
+                    if !v2 {
+                        details.explanation.clear();
+                    }
+                    if details.is_empty() {
+                        return Clue::Pair(label(number), clue.clone());
+                    }
+                    let references = details.references.iter().map(|reference| CrossReference {
+                        direction: match reference.direction {
+                            Direction::Across => "Across".to_string(),
+                            Direction::Down => "Down".to_string(),
+                        },
+                        number: label(&reference.number),
+                    });
+                    Clue::Object(ClueObject {
+                        number: Some(label(number)),
+                        numbers: Vec::new(),
+                        label: None,
+                        clue: clue.clone(),
+                        enumeration: Some(StringOrNum::String(details.enumeration.clone()))
+                            .filter(|_| !details.enumeration.is_empty()),
+                        cells: details.cells.iter().map(|&cell| [cell % chunk + 1, cell / chunk + 1]).collect(),
+                        references: references.collect(),
+                        hints: details.hints,
+                        explanation: details.explanation,
+                    })
+                })
+                .collect()
+        };
         let block_ref = &block;
         let solution: Vec<_> = grid
             .iter()
@@ -297,27 +605,40 @@ impl<'a> From<&'a Crossword> for IPuzRaw<'a> {
                 StringOrNum::String(s)
             })
             .collect();
-        let solution = solution.chunks(chunk).map(|c| c.to_vec()).collect();
+        let solution = xword.has_solution().then(|| solution.chunks(chunk).map(|c| c.to_vec()).collect());
 
         IPuzRaw {
-            version: Version,
+            version,
             kind: [Kind],
-            title,
-            copyright,
-            author,
-            notes,
+            title: Cow::Borrowed(title),
+            copyright: Cow::Borrowed(copyright),
+            author: Cow::Borrowed(author),
+            notes: Cow::Borrowed(notes),
+            date: Cow::Borrowed(date),
+            editor: Cow::Borrowed(editor),
+            publisher: Cow::Borrowed(publisher),
+            difficulty: Cow::Borrowed(difficulty),
+            intro: Cow::Borrowed(intro),
+            explanation: Cow::Borrowed(explanation),
+            url: Cow::Borrowed(url),
+            origin: Cow::Borrowed(origin),
             dimensions: Dimensions {
                 width: *width,
                 height: *height,
             },
             block,
             empty,
+            styles: HashMap::new(),
             puzzle,
             solution,
             clues: Clues {
-                across: Cow::Borrowed(across_clues),
-                down: Cow::Borrowed(down_clues),
+                across: clue_list(across_clues, across_details),
+                down: clue_list(down_clues, down_details),
             },
+            clueplacement: clue_placement.filter(|_| v2),
+            showenumerations: (v2 && *show_enumerations).then_some(true),
+            answer: answers.first().filter(|_| v2 && answers.len() == 1).cloned(),
+            answers: if v2 && answers.len() > 1 { answers.clone() } else { Vec::new() },
         }
     }
 }
@@ -333,19 +654,34 @@ impl<'a> TryFrom<IPuzRaw<'a>> for Crossword {
             copyright,
             author,
             notes,
+            date,
+            editor,
+            publisher,
+            difficulty,
+            intro,
+            explanation,
+            url,
+            origin,
             dimensions,
             block,
             empty,
+            styles,
             puzzle,
             solution,
             clues: Clues { across, down },
+            clueplacement,
+            showenumerations,
+            answer,
+            answers,
         } = ipuz;
         let mut issues = MultiError::new();
 
         if let Err(err) = validate_dimensions(dimensions, &puzzle) {
             issues.insert("puzzle", err);
         }
-        if let Err(err) = validate_dimensions(dimensions, &solution) {
+        if let Some(solution) = &solution
+            && let Err(err) = validate_dimensions(dimensions, solution)
+        {
             issues.insert("solution", err);
         }
 
@@ -354,6 +690,15 @@ impl<'a> TryFrom<IPuzRaw<'a>> for Crossword {
             return Err(issues);
         }
 
+        // without a solution, the puzzle's blocks are all we know of the grid.
+        let solution = solution.unwrap_or_else(|| {
+            let unknown = |cell: &LabeledCell| {
+                let sorn: &StringOrNum = cell.into();
+                if *sorn == block { block.clone() } else { StringOrNum::String(String::new()) }
+            };
+            puzzle.iter().map(|row| row.iter().map(unknown).collect()).collect()
+        });
+
         let width = dimensions.width as usize;
         let raw_grid: Result<Vec<_>, _> = solution
             .into_iter()
@@ -364,9 +709,10 @@ impl<'a> TryFrom<IPuzRaw<'a>> for Crossword {
                     return Ok(CrosswordCell::Wall);
                 }
                 let StringOrNum::String(elem) = elem else {
+                    let (row, col) = position(idx, width);
                     let err = DeserializeError::InvalidSolutionItem {
-                        row: idx / width,
-                        col: idx % width,
+                        row,
+                        col,
                         block: block.clone(),
                         actual: elem,
                     };
@@ -393,71 +739,218 @@ impl<'a> TryFrom<IPuzRaw<'a>> for Crossword {
             }
         };
 
+        let mut markup: Vec<_> = puzzle.iter().flatten().map(|cell| cell.markup(&styles)).collect();
+        for (idx, cell) in puzzle.iter().flatten().enumerate() {
+            let barred = cell.style(&styles).and_then(|spec| spec.barred.as_deref()).unwrap_or("");
+            for edge in barred.chars() {
+                match edge.to_ascii_uppercase() {
+                    'R' => markup[idx].bar_right = true,
+                    'B' => markup[idx].bar_bottom = true,
+                    'T' if idx >= width => markup[idx - width].bar_bottom = true,
+                    'L' if idx % width > 0 => markup[idx - 1].bar_right = true,
+                    _ => (),
+                }
+            }
+        }
+
         let grid = Grid {
             width: dimensions.width,
             height: dimensions.height,
             grid: &raw_grid,
+            markup: &markup,
         };
 
-        let puzzle = puzzle.into_iter().flatten();
-        let puzzle_error = zip(grid.iter_numbered(), puzzle).enumerate().try_for_each(
-            |(idx, (num_cell, lab_cell))| {
-                let lab_cell = lab_cell.to_value(&block, &empty).map_err(|error| {
-                    DeserializeError::LabeledCellError {
-                        row: idx / width,
-                        col: idx % width,
-                        error,
-                    }
-                })?;
-                let num_cell = num_cell.into();
-                if lab_cell != num_cell {
-                    let err = DeserializeError::InvalidNumbering {
-                        row: idx / width,
-                        col: idx % width,
-                        expected: num_cell,
-                        actual: lab_cell,
-                    };
-                    return Err(err);
+        let numbered: Vec<NumberedCell> = grid.iter_numbered().collect();
+        let mut labels = Vec::with_capacity(numbered.len());
+        // the first cell whose label isn't the usual number.
+        let mut misnumbered = None;
+        for (idx, (&num_cell, lab_cell)) in zip(&numbered, puzzle.iter().flatten()).enumerate() {
+            let (row, col) = position(idx, width);
+            let lab_cell = match lab_cell.to_value(&block, &empty) {
+                Ok(lab_cell) => lab_cell,
+                Err(error) => {
+                    issues.insert("puzzle", DeserializeError::LabeledCellError { row, col, error });
+                    break;
                 }
-                Ok(())
-            },
-        );
-        if let Err(error) = puzzle_error {
-            issues.insert("puzzle", error);
+            };
+            let num_cell = LabeledCellValue::from(num_cell);
+            labels.push(lab_cell.label());
+            if lab_cell.label() == num_cell.label() && lab_cell.is_block() == num_cell.is_block() {
+                continue;
+            }
+            // labels can't make walls out of cells, or cells out of walls.
+            let is_block = lab_cell.is_block() || num_cell.is_block();
+            let err = DeserializeError::InvalidNumbering { row, col, expected: num_cell, actual: lab_cell };
+            if is_block {
+                issues.insert("puzzle", err);
+                break;
+            }
+            misnumbered.get_or_insert(err);
         }
 
+        // Labels other than the usual numbering are the puzzle's own, and its clues refer to them.
+        // If the clues don't, the puzzle is more likely misnumbered.
+        let custom_labels = misnumbered.is_some();
+        let resolve_label = |label: StringOrNum, direction: Direction| -> Result<u16, DeserializeError> {
+            let label = label_text(label);
+            if !custom_labels {
+                return label.parse().map_err(|_| DeserializeError::UnknownLabel(label));
+            }
+            // labels may repeat, as long as only one entry in each direction starts at one.
+            match labelled_numbers(&numbered, &labels, direction, &label)[..] {
+                [number] => Ok(number),
+                [] => Err(DeserializeError::UnknownLabel(label)),
+                _ => Err(DeserializeError::AmbiguousLabel(label)),
+            }
+        };
         let (exp_across, exp_down) = grid.expected_grid_nums();
-        if let Err(err) = validate_clues(&exp_across, &across) {
-            issues.insert("clues.Across", err.into());
-        }
-        if let Err(err) = validate_clues(&exp_down, &down) {
-            issues.insert("clues.Down", err.into());
+        let resolve = |clues: ClueList, direction: Direction| -> Result<Vec<(u16, String, ClueDetails)>, DeserializeError> {
+            let mut resolved = clues
+                .into_iter()
+                .map(|clue| -> Result<_, DeserializeError> {
+                    let object = match clue {
+                        Clue::Pair(label, clue) => {
+                            return Ok((resolve_label(label, direction)?, clue, ClueDetails::default()));
+                        }
+                        Clue::Object(object) => object,
+                    };
+                    let ClueObject { number, numbers, label, clue, enumeration, cells, references, hints, explanation } =
+                        object;
+                    let number = match (number, numbers.len()) {
+                        (Some(number), 0) => number,
+                        (None, 1) => numbers.into_iter().next().unwrap(),
+                        (None, 0) => label.ok_or(DeserializeError::MissingClueNumber)?,
+                        (number, _) => {
+                            let numbers: Vec<_> = number.into_iter().chain(numbers).map(label_text).collect();
+                            return Err(DeserializeError::MultiEntryClue(numbers.join(", ")));
+                        }
+                    };
+                    let cells = cells
+                        .into_iter()
+                        .map(|[col, row]| {
+                            if !(1..=width).contains(&col) || !(1..=dimensions.height as usize).contains(&row) {
+                                return Err(DeserializeError::ClueCellOutOfBounds { col, row });
+                            }
+                            Ok((row - 1) * width + col - 1)
+                        })
+                        .collect::<Result<_, DeserializeError>>()?;
+                    let references = references
+                        .into_iter()
+                        .map(|CrossReference { direction, number }| {
+                            let direction =
+                                direction_from_ipuz(&direction).ok_or(DeserializeError::UnknownDirection(direction))?;
+                            let label = number.clone();
+                            let number = resolve_label(number, direction)?;
+                            let entries = if direction == Direction::Across { &exp_across } else { &exp_down };
+                            if !entries.contains(&number) {
+                                return Err(DeserializeError::UnknownLabel(label_text(label)));
+                            }
+                            Ok(ClueReference { direction, number })
+                        })
+                        .collect::<Result<_, DeserializeError>>()?;
+                    let enumeration = match enumeration {
+                        Some(StringOrNum::Num(num)) => num.to_string(),
+                        Some(StringOrNum::String(enumeration)) => enumeration,
+                        None => String::new(),
+                    };
+                    let details = ClueDetails { enumeration, cells, references, hints, explanation };
+                    Ok((resolve_label(number, direction)?, clue, details))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if custom_labels {
+                resolved.sort_by_key(|&(number, _, _)| number);
+            }
+            Ok(resolved)
+        };
+
+        let mut clue_lists = [Vec::new(), Vec::new()];
+        let mut detail_lists = [Vec::new(), Vec::new()];
+        for (idx, (key, clues, direction, expected)) in [
+            ("clues.Across", across, Direction::Across, &exp_across),
+            ("clues.Down", down, Direction::Down, &exp_down),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            match resolve(clues, direction) {
+                Ok(resolved) => {
+                    let (clues, details): (Vec<_>, Vec<_>) =
+                        resolved.into_iter().map(|(number, clue, details)| ((number, clue), details)).unzip();
+                    if let Err(err) = validate_clues(expected, &clues) {
+                        issues.insert(key, err.into());
+                    }
+                    clue_lists[idx] = clues;
+                    if !details.iter().all(ClueDetails::is_empty) {
+                        detail_lists[idx] = details;
+                    }
+                }
+                Err(err) => {
+                    issues.insert(key, err);
+                    if let Some(err) = misnumbered.take() {
+                        issues.insert("puzzle", err);
+                    }
+                }
+            }
         }
 
         if !issues.is_empty() {
             return Err(issues);
         }
 
-        let xword = Crossword {
-            title: title.to_owned(),
-            copyright: copyright.to_owned(),
-            author: author.to_owned(),
-            notes: notes.to_owned(),
+        let [across_clues, down_clues] = clue_lists;
+        let [across_details, down_details] = detail_lists;
+        let args = CrosswordArgs {
+            title: title.into_owned(),
+            copyright: copyright.into_owned(),
+            author: author.into_owned(),
+            notes: notes.into_owned(),
+            date: date.into_owned(),
+            editor: editor.into_owned(),
+            publisher: publisher.into_owned(),
+            difficulty: difficulty.into_owned(),
+            intro: intro.into_owned(),
+            explanation: explanation.into_owned(),
+            url: url.into_owned(),
+            origin: origin.into_owned(),
             width: dimensions.width,
             height: dimensions.height,
-            across_clues: across.to_vec(),
-            down_clues: down.to_vec(),
+            across_clues,
+            down_clues,
+            across_clues_by_label: Vec::new(),
+            down_clues_by_label: Vec::new(),
+            across_details,
+            down_details,
             grid: raw_grid,
+            markup,
+            labels: if custom_labels { labels } else { Vec::new() },
+            player: None,
+            clue_placement: clueplacement,
+            show_enumerations: showenumerations.unwrap_or(false),
+            answers: answer.into_iter().chain(answers).collect(),
         };
-        Ok(xword)
+        // the checks every puzzle gets, eg on its rebuses, which ipuz itself doesn't limit.
+        args.validate().map_err(|errs| errs.map(DeserializeError::from))
     }
 }
 
 impl Crossword {
     pub fn to_ipuz(&self) -> Vec<u8> {
-        let ipuz: IPuzRaw = self.into();
+        self.to_ipuz_version(IPuzVersion::default())
+    }
+
+    /// Write an `.ipuz` file of a particular version of the spec.
+    pub fn to_ipuz_version(&self, version: IPuzVersion) -> Vec<u8> {
+        let ipuz = IPuzRaw::new(self, version);
         serde_json::to_vec(&ipuz).expect("serializable") // TODO: don't panic
     }
+
+    /// Read a `Crossword` from the contents of an `.ipuz` file.
+    pub fn from_ipuz(bytes: &[u8]) -> Result<Crossword, MultiError<DeserializeError>> {
+        let ipuz: IPuzRaw = serde_json::from_slice(bytes).map_err(|err| {
+            MultiError::from([("json", err.into())])
+        })?;
+        ipuz.try_into()
+    }
 }
 
 #[cfg(test)]
@@ -471,4 +964,316 @@ mod tests {
         let xword: Crossword = ipuz.try_into().unwrap();
         xword.to_ipuz();
     }
+
+    #[test]
+    fn test_from_ipuz() {
+        let ipuz = include_bytes!("test_files/Ups and Downs.ipuz");
+        let xword = Crossword::from_ipuz(ipuz).unwrap();
+        assert_eq!(xword.title, "🫛 Ups and Downs");
+        let round_trip = Crossword::from_ipuz(&xword.to_ipuz()).unwrap();
+        assert_eq!(round_trip.across_clues, xword.across_clues);
+        assert_eq!(round_trip.down_clues, xword.down_clues);
+    }
+
+    #[test]
+    fn test_no_solution() {
+        let ipuz = include_bytes!("test_files/Ups and Downs.ipuz");
+        let mut xword = Crossword::from_ipuz(ipuz).unwrap();
+        for cell in xword.grid.iter_mut().filter(|cell| !cell.is_wall()) {
+            *cell = CrosswordCell::Empty;
+        }
+        let ipuz = xword.to_ipuz();
+        let raw: serde_json::Value = serde_json::from_slice(&ipuz).unwrap();
+        assert!(raw.get("solution").is_none());
+
+        let round_trip = Crossword::from_ipuz(&ipuz).unwrap();
+        assert!(!round_trip.has_solution());
+        let walls = |xword: &Crossword| xword.grid.iter().map(CrosswordCell::is_wall).collect::<Vec<_>>();
+        assert_eq!(walls(&round_trip), walls(&xword));
+        assert_eq!(round_trip.across_clues, xword.across_clues);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let ipuz = include_bytes!("test_files/Ups and Downs.ipuz");
+        let mut xword = Crossword::from_ipuz(ipuz).unwrap();
+        assert_eq!(xword.date, "");
+        xword.date = "01/31/2024".to_string();
+        xword.publisher = "Crosshare".to_string();
+        xword.explanation = "Well done!".to_string();
+        xword.url = "https://crosshare.org".to_string();
+
+        let ipuz = String::from_utf8(xword.to_ipuz()).unwrap();
+        assert!(ipuz.contains(r#""publisher":"Crosshare""#));
+        assert!(!ipuz.contains("editor"));
+        let round_trip = Crossword::from_ipuz(ipuz.as_bytes()).unwrap();
+        assert_eq!(round_trip.date, xword.date);
+        assert_eq!(round_trip.publisher, xword.publisher);
+        assert_eq!(round_trip.explanation, xword.explanation);
+        assert_eq!(round_trip.url, xword.url);
+    }
+
+    #[test]
+    fn test_from_ipuz_errors() {
+        let errors = Crossword::from_ipuz(b"{").unwrap_err().into_error_map();
+        assert!(matches!(errors["json"], DeserializeError::Json(_)));
+
+        let ipuz = include_str!("test_files/Ups and Downs.ipuz").replacen("{\"cell\":2}", "{\"cell\":0}", 1);
+        let errors = Crossword::from_ipuz(ipuz.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(errors["puzzle"], DeserializeError::InvalidNumbering { row: 1, col: 9, .. }));
+
+        // ipuz doesn't limit rebuses, but everything else does.
+        let mut xword = Crossword::from_ipuz(include_bytes!("test_files/Ups and Downs.ipuz")).unwrap();
+        let letters = xword.grid.iter_mut().filter(|cell| !cell.is_wall());
+        for (idx, cell) in letters.take(100).enumerate() {
+            *cell = CrosswordCell::Rebus(format!("R{idx}"));
+        }
+        let errors = Crossword::from_ipuz(&xword.to_ipuz()).unwrap_err().into_error_map();
+        assert!(matches!(errors["grid"], DeserializeError::Validation(ValidationError::TooManyRebuses(100))));
+    }
+
+    #[test]
+    fn test_markup() {
+        let ipuz = include_bytes!("test_files/Ups and Downs.ipuz");
+        let mut xword = Crossword::from_ipuz(ipuz).unwrap();
+        xword.markup[7].circled = true;
+        xword.markup[8].shaded = true;
+        xword.markup[9].given = true;
+
+        let ipuz = String::from_utf8(xword.to_ipuz()).unwrap();
+        assert!(ipuz.contains(r#"{"cell":1,"style":{"shapebg":"circle"}}"#));
+        assert!(ipuz.contains(r#"{"cell":2,"style":{"highlight":true}}"#));
+        assert!(ipuz.contains(r#"{"cell":3,"value":"#));
+
+        let xword = Crossword::from_ipuz(ipuz.as_bytes()).unwrap();
+        assert!(xword.markup[7].circled);
+        assert!(xword.markup[8].shaded);
+        assert!(xword.markup[9].given);
+        assert!(xword.markup[10..].iter().all(|m| m == &CellMarkup::default()));
+    }
+
+    #[test]
+    fn test_styles() {
+        let ipuz = r##"{
+            "version": "http://ipuz.org/v1",
+            "kind": ["http://ipuz.org/crossword#1"],
+            "dimensions": {"width": 3, "height": 2},
+            "styles": {"circled": {"shapebg": "circle", "divided": "/"}},
+            "puzzle": [
+                [{"cell": 1, "style": {"color": "ff0000", "colortext": 3}}, {"cell": 2, "style": {"barred": "L"}}, 3],
+                [{"cell": 4, "style": "circled"}, {"cell": 0, "style": {"colortext": "#00ff00"}}, 0]
+            ],
+            "solution": [["A", "B", "C"], ["D", "E", "F"]],
+            "clues": {"Across": [[2, "bc"], [4, "def"]], "Down": [[1, "ad"], [2, "be"], [3, "cf"]]}
+        }"##;
+        let xword = Crossword::from_ipuz(ipuz.as_bytes()).unwrap();
+        assert_eq!(xword.markup[0].color.as_deref(), Some("#ff0000"));
+        assert_eq!(xword.markup[0].text_color, None);
+        assert!(xword.markup[0].bar_right);
+        assert!(!xword.markup[1].bar_right);
+        assert!(xword.markup[3].circled);
+        assert_eq!(xword.markup[4].text_color.as_deref(), Some("#00ff00"));
+
+        let ipuz = String::from_utf8(xword.to_ipuz()).unwrap();
+        assert!(ipuz.contains(r#"{"cell":1,"style":{"color":"ff0000","barred":"R"}}"#));
+        let round_trip = Crossword::from_ipuz(ipuz.as_bytes()).unwrap();
+        assert_eq!(round_trip.markup, xword.markup);
+    }
+
+    #[test]
+    fn test_clue_objects() {
+        let ipuz = r##"{
+            "version": "http://ipuz.org/v1",
+            "kind": ["http://ipuz.org/crossword#1"],
+            "dimensions": {"width": 3, "height": 2},
+            "puzzle": [[1, 2, 3], [4, 0, 0]],
+            "solution": [["A", "B", "C"], ["D", "E", "F"]],
+            "clues": {
+                "Across": [{"number": 1, "clue": "abc", "enumeration": 3, "hints": ["starts with A"]}, [4, "def"]],
+                "Down": [
+                    {"number": "1", "clue": "ad", "references": [{"direction": "Across", "number": 1}]},
+                    {"number": 2, "clue": "be", "cells": [[2, 1], [2, 2]]},
+                    [3, "cf"]
+                ]
+            }
+        }"##;
+        let xword = Crossword::from_ipuz(ipuz.as_bytes()).unwrap();
+        assert_eq!(xword.across_clues, [(1, "abc".to_string()), (4, "def".to_string())]);
+        assert_eq!(xword.across_details[0].enumeration, "3");
+        assert_eq!(xword.across_details[0].hints, ["starts with A"]);
+        assert!(xword.across_details[1].is_empty());
+        let reference = ClueReference { direction: Direction::Across, number: 1 };
+        assert_eq!(xword.down_details[0].references, [reference]);
+        assert_eq!(xword.down_details[1].cells, [1, 4]);
+
+        let exported = String::from_utf8(xword.to_ipuz()).unwrap();
+        assert!(exported.contains(r#"[4,"def"]"#));
+        assert!(exported.contains(r#"{"number":2,"clue":"be","cells":[[2,1],[2,2]]}"#));
+        let round_trip = Crossword::from_ipuz(exported.as_bytes()).unwrap();
+        assert_eq!(round_trip.across_details, xword.across_details);
+        assert_eq!(round_trip.down_details, xword.down_details);
+
+        let sideways = ipuz.replace(r#""direction": "Across""#, r#""direction": "Sideways""#);
+        let errors = Crossword::from_ipuz(sideways.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(&errors["clues.Down"], DeserializeError::UnknownDirection(d) if d == "Sideways"));
+
+        // there's no 2-Across to refer to.
+        let dangling = ipuz.replace(r#""number": 1}]"#, r#""number": 2}]"#);
+        let errors = Crossword::from_ipuz(dangling.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(&errors["clues.Down"], DeserializeError::UnknownLabel(label) if label == "2"));
+        let mut args = crate::CrosswordArgs::from(xword);
+        args.down_details[0].references[0].number = 2;
+        let errors = args.validate().unwrap_err().into_error_map();
+        assert!(matches!(
+            errors["down_details"],
+            crate::ValidationError::InvalidClueReference { number: 1, direction: Direction::Across, reference: 2 },
+        ));
+    }
+
+    #[test]
+    fn test_clue_cell_walls() {
+        let ipuz = r##"{
+            "version": "http://ipuz.org/v1",
+            "kind": ["http://ipuz.org/crossword#1"],
+            "dimensions": {"width": 2, "height": 2},
+            "puzzle": [[1, 2], [3, "#"]],
+            "solution": [["A", "B"], ["C", "#"]],
+            "clues": {
+                "Across": [[1, "ab"]],
+                "Down": [{"number": 1, "clue": "ac", "cells": [[1, 1], [2, 2]]}]
+            }
+        }"##;
+        let errors = Crossword::from_ipuz(ipuz.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(
+            errors["down_details"],
+            DeserializeError::Validation(ValidationError::InvalidClueCell { number: 1, cell: 3 }),
+        ));
+    }
+
+    #[test]
+    fn test_clue_object_forms() {
+        // a `label` in place of a `number`, a lone `numbers`, and no `clue` at all.
+        let ipuz = r##"{
+            "version": "http://ipuz.org/v1",
+            "kind": ["http://ipuz.org/crossword#1"],
+            "dimensions": {"width": 2, "height": 2},
+            "puzzle": [[1, 2], [3, 0]],
+            "solution": [["A", "B"], ["C", "D"]],
+            "clues": {
+                "Across": [{"label": "1", "clue": "ab"}, {"numbers": [3], "clue": "cd"}],
+                "Down": [{"number": 1}, [2, "bd"]]
+            }
+        }"##;
+        let xword = Crossword::from_ipuz(ipuz.as_bytes()).unwrap();
+        assert_eq!(xword.across_clues, [(1, "ab".to_string()), (3, "cd".to_string())]);
+        assert_eq!(xword.down_clues[0], (1, String::new()));
+
+        let spanning = ipuz.replace(r#""numbers": [3]"#, r#""numbers": [3, "2"]"#);
+        let errors = Crossword::from_ipuz(spanning.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(&errors["clues.Across"], DeserializeError::MultiEntryClue(numbers) if numbers == "3, 2"));
+
+        let unnumbered = ipuz.replace(r#"{"number": 1}"#, r#"{"clue": "ac"}"#);
+        let errors = Crossword::from_ipuz(unnumbered.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(errors["clues.Down"], DeserializeError::MissingClueNumber));
+    }
+
+    #[test]
+    fn test_versions() {
+        let ipuz = r##"{
+            "version": "http://ipuz.org/v2",
+            "kind": ["http://ipuz.org/crossword#1"],
+            "dimensions": {"width": 2, "height": 2},
+            "clueplacement": "before",
+            "showenumerations": true,
+            "answers": ["AD", "BC"],
+            "puzzle": [[1, 2], [3, 0]],
+            "solution": [["A", "B"], ["C", "D"]],
+            "clues": {
+                "Across": [{"number": 1, "clue": "ab", "explanation": "A, then B"}, [3, "cd"]],
+                "Down": [[1, "ac"], [2, "bd"]]
+            }
+        }"##;
+        let xword = Crossword::from_ipuz(ipuz.as_bytes()).unwrap();
+        assert_eq!(xword.clue_placement, Some(CluePlacement::Before));
+        assert!(xword.show_enumerations);
+        assert_eq!(xword.answers, ["AD", "BC"]);
+        assert_eq!(xword.across_details[0].explanation, "A, then B");
+
+        let v2 = xword.to_ipuz_version(IPuzVersion::V2);
+        let round_trip = Crossword::from_ipuz(&v2).unwrap();
+        assert_eq!(round_trip.clue_placement, xword.clue_placement);
+        assert_eq!(round_trip.answers, xword.answers);
+        assert_eq!(round_trip.across_details, xword.across_details);
+
+        let v1 = String::from_utf8(xword.to_ipuz()).unwrap();
+        assert!(v1.contains("http://ipuz.org/v1"));
+        for field in ["clueplacement", "showenumerations", "answers", "explanation"] {
+            assert!(!v1.contains(field), "{field} isn't v1");
+        }
+        assert!(v1.contains(r#"[1,"ab"]"#));
+
+        let errors = Crossword::from_ipuz(ipuz.replace("v2", "v3").as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(errors["json"], DeserializeError::Json(_)));
+    }
+
+    #[test]
+    fn test_labels() {
+        // "A" and "*" stand in for 1 and 4.
+        let ipuz = r##"{
+            "version": "http://ipuz.org/v1",
+            "kind": ["http://ipuz.org/crossword#1"],
+            "dimensions": {"width": 3, "height": 2},
+            "puzzle": [["A", 2, 3], ["*", 0, 0]],
+            "solution": [["A", "B", "C"], ["D", "E", "F"]],
+            "clues": {"Across": [["A", "abc"], ["*", "def"]], "Down": [["A", "ad"], [2, "be"], [3, "cf"]]}
+        }"##;
+        let xword = Crossword::from_ipuz(ipuz.as_bytes()).unwrap();
+        assert_eq!(xword.labels, ["A", "2", "3", "*", "", ""]);
+        assert_eq!(xword.across_clues, [(1, "abc".to_string()), (4, "def".to_string())]);
+        assert_eq!(xword.down_clues[0], (1, "ad".to_string()));
+
+        let round_trip = Crossword::from_ipuz(&xword.to_ipuz()).unwrap();
+        assert_eq!(round_trip.labels, xword.labels);
+        assert_eq!(round_trip.across_clues, xword.across_clues);
+        assert!(matches!(
+            xword.export(crate::FileFormat::Puz12),
+            Err(crate::ValidationError::UnsupportedLabels { .. })
+        ));
+
+        // labels which match neither the usual numbering nor the clues.
+        let ipuz = ipuz.replace(r#"["*", "def"]"#, r#"[4, "def"]"#);
+        let errors = Crossword::from_ipuz(ipuz.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(&errors["clues.Across"], DeserializeError::UnknownLabel(label) if label == "4"));
+        assert!(matches!(errors["puzzle"], DeserializeError::InvalidNumbering { row: 1, col: 1, .. }));
+    }
+
+    #[test]
+    fn test_repeated_labels() {
+        let ipuz = r##"{
+            "version": "http://ipuz.org/v1",
+            "kind": ["http://ipuz.org/crossword#1"],
+            "dimensions": {"width": 3, "height": 2},
+            "puzzle": [["*", "#", "*"], [0, 0, 0]],
+            "solution": [["A", "#", "C"], ["D", "E", "F"]],
+            "clues": {"Across": [["*", "def"]], "Down": [["*", "ad"], [3, "cf"]]}
+        }"##;
+        let errors = Crossword::from_ipuz(ipuz.as_bytes()).unwrap_err().into_error_map();
+        // the first "*" starts 1-Down, and the second 2-Down.
+        assert!(matches!(&errors["clues.Down"], DeserializeError::AmbiguousLabel(label) if label == "*"));
+        assert!(matches!(&errors["clues.Across"], DeserializeError::UnknownLabel(label) if label == "*"));
+
+        let ipuz = ipuz.replace(r#"[0, 0, 0]"#, r#"["*", 0, 0]"#).replace(r#"[3, "cf"]"#, r#"["*", "cf"]"#);
+        let errors = Crossword::from_ipuz(ipuz.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(&errors["clues.Down"], DeserializeError::AmbiguousLabel(label) if label == "*"));
+
+        let ipuz = ipuz.replace(r##"[["*", "#", "*"]"##, r##"[["A", "#", "B"]"##);
+        let ipuz = ipuz.replace(r#"[["*", "ad"], ["*", "cf"]]"#, r#"[["A", "ad"], ["B", "cf"]]"#);
+        let xword = Crossword::from_ipuz(ipuz.as_bytes()).unwrap();
+        assert_eq!(xword.labels, ["A", "", "B", "*", "", ""]);
+        assert_eq!(xword.across_clues, [(3, "def".to_string())]);
+        assert_eq!(xword.clue_label(3), Some("*".to_string()));
+        assert_eq!(xword.clue_number(Direction::Across, "*"), Some(3));
+        assert_eq!(xword.clue_number(Direction::Down, "B"), Some(2));
+        assert_eq!(xword.clue_number(Direction::Down, "*"), None);
+    }
 }
@@ -0,0 +1,568 @@
+// Crossword Compiler's `.jpz` format: XML, sometimes zipped.
+//
+// <crossword-compiler-applet>
+//   <rectangular-puzzle>
+//     <metadata><title/><creator/><copyright/><description/></metadata>
+//     <crossword>
+//       <grid width="15" height="15">
+//         <cell x="1" y="1" solution="A" number="1" background-shape="circle"/>
+//         <cell x="2" y="1" type="block"/>
+//         ...
+//       </grid>
+//       <word id="1" x="1-5" y="1"/>
+//       ...
+//       <clues><title><b>Across</b></title><clue word="1" number="1">Clue</clue>...</clues>
+//       <clues><title><b>Down</b></title>...</clues>
+//     </crossword>
+//   </rectangular-puzzle>
+// </crossword-compiler-applet>
+//
+// Coordinates are 1-based. Clues refer to words, which list their cells;
+// we number clues by the cell each word starts at rather than trusting the file.
+
+use crate::multi_error::MultiError;
+use crate::xml::{self, Element, XmlError, escape, open_tag};
+use crate::{CellMarkup, Crossword, CrosswordArgs, CrosswordCell, Direction, Entry, NumberedCell, ValidationError};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const APPLET_NS: &str = "http://crossword.info/xml/crossword-compiler-applet";
+const PUZZLE_NS: &str = "http://crossword.info/xml/rectangular-puzzle";
+const SHADED_COLOR: &str = "#DCDCDC";
+/// The largest file we'll unzip: far larger than any crossword.
+const MAX_UNZIPPED_LEN: usize = 16 << 20;
+
+#[derive(thiserror::Error, Debug)]
+pub enum JpzError {
+    #[error("invalid or unsupported zip archive")]
+    InvalidZip,
+    #[error("{0}")]
+    Xml(#[from] XmlError),
+    #[error("missing <{0}> element")]
+    MissingElement(&'static str),
+    #[error("invalid {attribute}={value:?} on <{element}>")]
+    InvalidAttribute {
+        element: &'static str,
+        attribute: &'static str,
+        value: String,
+    },
+    #[error("the cell at row {y}, column {x} is outside the grid")]
+    CellOutOfBounds { x: usize, y: usize },
+    #[error("missing the cell at row {y}, column {x}")]
+    MissingCell { x: usize, y: usize },
+    #[error("clue refers to unknown word {0:?}")]
+    UnknownWord(String),
+    #[error("word {0:?} doesn't start at a numbered cell")]
+    UnnumberedWord(String),
+    #[error("{0}")]
+    Validation(#[from] ValidationError),
+}
+
+impl Crossword {
+    pub(crate) fn to_jpz(&self) -> Vec<u8> {
+        let width = self.width as usize;
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        open_tag(&mut out, "crossword-compiler-applet", &[("xmlns", APPLET_NS)], false);
+        open_tag(&mut out, "rectangular-puzzle", &[("xmlns", PUZZLE_NS)], false);
+
+        out.push_str("<metadata>");
+        for (tag, text) in [
+            ("title", &self.title),
+            ("creator", &self.author),
+            ("copyright", &self.copyright),
+            ("description", &self.notes),
+        ] {
+            if !text.is_empty() {
+                write!(out, "<{tag}>{}</{tag}>", escape(text)).unwrap();
+            }
+        }
+        out.push_str("</metadata>\n<crossword>\n");
+
+        let (width_s, height_s) = (self.width.to_string(), self.height.to_string());
+        open_tag(&mut out, "grid", &[("width", &width_s), ("height", &height_s)], false);
+        out.push('\n');
+        let cells = self.grid.iter().zip(&self.markup).zip(self.cell_labels());
+        for (idx, ((cell, markup), label)) in cells.enumerate() {
+            let x = (idx % width + 1).to_string();
+            let y = (idx / width + 1).to_string();
+            let mut attrs = vec![("x", x.as_str()), ("y", y.as_str())];
+            let solution = match cell {
+                CrosswordCell::Wall => {
+                    attrs.push(("type", "block"));
+                    None
+                }
+                CrosswordCell::Empty => None,
+                CrosswordCell::Char(c) => Some(c.to_string()),
+                CrosswordCell::Rebus(s) => Some(s.clone()),
+            };
+            if let Some(solution) = &solution {
+                attrs.push(("solution", solution));
+            }
+            if !label.is_empty() {
+                attrs.push(("number", &label));
+            }
+            if markup.circled {
+                attrs.push(("background-shape", "circle"));
+            }
+            if markup.shaded {
+                attrs.push(("background-color", SHADED_COLOR));
+            }
+            if markup.given {
+                attrs.push(("hint", "true"));
+            }
+            if markup.bar_right {
+                attrs.push(("right-bar", "true"));
+            }
+            if markup.bar_bottom {
+                attrs.push(("bottom-bar", "true"));
+            }
+            open_tag(&mut out, "cell", &attrs, true);
+            out.push('\n');
+        }
+        out.push_str("</grid>\n");
+
+        let entries = self.entries();
+        let mut word_id = 0;
+        let mut word_ids = |direction: Direction, out: &mut String| -> HashMap<u16, String> {
+            let mut ids = HashMap::new();
+            for Entry { number, cells, .. } in entries.iter().filter(|entry| entry.direction == direction) {
+                word_id += 1;
+                let id = word_id.to_string();
+                let ((first_row, first_col), (last_row, last_col)) = (cells[0], cells[cells.len() - 1]);
+                let is_run = match direction {
+                    Direction::Across => cells.iter().enumerate().all(|(idx, &cell)| cell == (first_row, first_col + idx)),
+                    Direction::Down => cells.iter().enumerate().all(|(idx, &cell)| cell == (first_row + idx, first_col)),
+                };
+                if is_run {
+                    let range = |a: usize, b: usize| if a == b { a.to_string() } else { format!("{a}-{b}") };
+                    let x = range(first_col + 1, last_col + 1);
+                    let y = range(first_row + 1, last_row + 1);
+                    open_tag(out, "word", &[("id", &id), ("x", &x), ("y", &y)], true);
+                } else {
+                    // clues with cells of their own, which aren't a run, list them one by one.
+                    open_tag(out, "word", &[("id", &id)], false);
+                    for (row, col) in cells {
+                        open_tag(out, "cells", &[("x", &(col + 1).to_string()), ("y", &(row + 1).to_string())], true);
+                    }
+                    out.push_str("</word>");
+                }
+                out.push('\n');
+                ids.insert(*number, id);
+            }
+            ids
+        };
+        let across_ids = word_ids(Direction::Across, &mut out);
+        let down_ids = word_ids(Direction::Down, &mut out);
+        let labels = self.clue_labels();
+
+        for (title, clues, ids) in [
+            ("Across", &self.across_clues, across_ids),
+            ("Down", &self.down_clues, down_ids),
+        ] {
+            writeln!(out, "<clues ordering=\"normal\"><title><b>{title}</b></title>").unwrap();
+            for (number, clue) in clues {
+                open_tag(&mut out, "clue", &[("word", &ids[number]), ("number", &labels[number])], false);
+                writeln!(out, "{}</clue>", escape(clue)).unwrap();
+            }
+            out.push_str("</clues>\n");
+        }
+
+        out.push_str("</crossword>\n</rectangular-puzzle>\n</crossword-compiler-applet>\n");
+        out.into_bytes()
+    }
+
+    /// Read a `Crossword` from the contents of a `.jpz` file, zipped or not.
+    pub fn from_jpz(bytes: &[u8]) -> Result<Crossword, MultiError<JpzError>> {
+        let bytes = if bytes.starts_with(b"PK\x03\x04") {
+            Cow::Owned(unzip_first(bytes).ok_or_else(|| MultiError::from([("zip", JpzError::InvalidZip)]))?)
+        } else {
+            Cow::Borrowed(bytes)
+        };
+        // The xml declaration says, but in practice it's utf-8 or windows-1252.
+        let text = match std::str::from_utf8(&bytes) {
+            Ok(text) => Cow::Borrowed(text),
+            Err(_) => encoding_rs::WINDOWS_1252.decode_without_bom_handling(&bytes).0,
+        };
+        let root = xml::parse(&text).map_err(|err| MultiError::from([("xml", err.into())]))?;
+
+        let missing = |tag| MultiError::from([("xml", JpzError::MissingElement(tag))]);
+        let puzzle = root.child("rectangular-puzzle").unwrap_or(&root);
+        let crossword = puzzle.child("crossword").ok_or_else(|| missing("crossword"))?;
+        let grid_elem = crossword.child("grid").ok_or_else(|| missing("grid"))?;
+
+        let (width, height, grid, markup, labels) =
+            parse_grid(grid_elem).map_err(|err| MultiError::from([("grid", err)]))?;
+        let mut args = CrosswordArgs {
+            width,
+            height,
+            grid,
+            markup,
+            labels,
+            ..Default::default()
+        };
+        if let Some(metadata) = puzzle.child("metadata") {
+            let text = |tag| metadata.child(tag).map(|elem| elem.text().trim().to_owned()).unwrap_or_default();
+            args.title = text("title");
+            args.author = text("creator");
+            args.copyright = text("copyright");
+            args.notes = text("description");
+        }
+        if args.notes.is_empty()
+            && let Some(instructions) = puzzle.child("instructions")
+        {
+            args.notes = instructions.text().trim().to_owned();
+        }
+
+        let numbers: Vec<Option<u16>> = args
+            .grid()
+            .iter_numbered()
+            .map(|cell| match cell {
+                NumberedCell::Numbered { number, .. } => Some(number),
+                _ => None,
+            })
+            .collect();
+        let words = word_numbers(crossword, width as usize, height as usize, &numbers)
+            .map_err(|err| MultiError::from([("words", err)]))?;
+
+        let mut issues = MultiError::new();
+        for (idx, clues) in crossword.children("clues").enumerate() {
+            let title = clues.child("title").map(|t| t.text().to_lowercase()).unwrap_or_default();
+            let (key, dest) = if title.contains("down") || (!title.contains("across") && idx == 1) {
+                ("down_clues", &mut args.down_clues)
+            } else {
+                ("across_clues", &mut args.across_clues)
+            };
+            match parse_clues(clues, &words) {
+                Ok(parsed) => dest.extend(parsed),
+                Err(err) => issues.insert(key, err),
+            }
+        }
+        if !issues.is_empty() {
+            return Err(issues);
+        }
+        args.validate().map_err(|errs| errs.map(JpzError::from))
+    }
+}
+
+fn parse_attr<T: std::str::FromStr>(
+    elem: &Element,
+    element: &'static str,
+    attribute: &'static str,
+) -> Result<Option<T>, JpzError> {
+    let Some(value) = elem.attr(attribute) else {
+        return Ok(None);
+    };
+    let invalid = || JpzError::InvalidAttribute { element, attribute, value: value.to_owned() };
+    value.trim().parse().map(Some).map_err(|_| invalid())
+}
+
+#[allow(clippy::type_complexity)]
+fn parse_grid(elem: &Element) -> Result<(u8, u8, Vec<CrosswordCell>, Vec<CellMarkup>, Vec<String>), JpzError> {
+    let width: u8 = parse_attr(elem, "grid", "width")?.ok_or(JpzError::MissingElement("grid width"))?;
+    let height: u8 = parse_attr(elem, "grid", "height")?.ok_or(JpzError::MissingElement("grid height"))?;
+    let (w, h) = (width as usize, height as usize);
+
+    let mut grid = vec![None; w * h];
+    let mut markup = vec![CellMarkup::default(); w * h];
+    let mut labels = vec![String::new(); w * h];
+    for cell in elem.children("cell") {
+        let x: usize = parse_attr(cell, "cell", "x")?.unwrap_or(0);
+        let y: usize = parse_attr(cell, "cell", "y")?.unwrap_or(0);
+        if !(1..=w).contains(&x) || !(1..=h).contains(&y) {
+            return Err(JpzError::CellOutOfBounds { x, y });
+        }
+        let idx = (y - 1) * w + (x - 1);
+        grid[idx] = Some(match (cell.attr("type"), cell.attr("solution")) {
+            (Some("block" | "void"), _) => CrosswordCell::Wall,
+            (_, None) | (_, Some("")) => CrosswordCell::Empty,
+            (_, Some(solution)) => {
+                let mut chars = solution.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => CrosswordCell::Char(c),
+                    _ => CrosswordCell::Rebus(solution.to_owned()),
+                }
+            }
+        });
+        labels[idx] = cell.attr("number").unwrap_or("").trim().to_owned();
+        let background = cell.attr("background-color").unwrap_or("");
+        markup[idx] = CellMarkup {
+            circled: cell.attr("background-shape") == Some("circle"),
+            shaded: !background.is_empty() && !background.eq_ignore_ascii_case("#ffffff"),
+            given: cell.attr("hint") == Some("true"),
+            bar_right: cell.attr("right-bar") == Some("true"),
+            bar_bottom: cell.attr("bottom-bar") == Some("true"),
+            ..Default::default()
+        };
+    }
+
+    let grid = grid
+        .into_iter()
+        .enumerate()
+        .map(|(idx, cell)| cell.ok_or(JpzError::MissingCell { x: idx % w + 1, y: idx / w + 1 }))
+        .collect::<Result<_, _>>()?;
+    // some files leave numbering to the reader.
+    if labels.iter().all(String::is_empty) {
+        labels.clear();
+    }
+    Ok((width, height, grid, markup, labels))
+}
+
+/// Map of word id to the clue number at the word's first cell.
+fn word_numbers(
+    crossword: &Element,
+    width: usize,
+    height: usize,
+    numbers: &[Option<u16>],
+) -> Result<HashMap<String, u16>, JpzError> {
+    // "3" or "3-7": either way, the word starts at the first.
+    let start = |elem: &Element, attribute| -> Result<Option<usize>, JpzError> {
+        let Some(value) = elem.attr(attribute) else {
+            return Ok(None);
+        };
+        let first = value.split('-').next().unwrap_or(value).trim();
+        first.parse().map(Some).map_err(|_| JpzError::InvalidAttribute {
+            element: "word",
+            attribute,
+            value: value.to_owned(),
+        })
+    };
+
+    let mut words = HashMap::new();
+    for word in crossword.children("word") {
+        let id = word.attr("id").unwrap_or("").to_owned();
+        let first_cell = match (start(word, "x")?, start(word, "y")?) {
+            (Some(x), Some(y)) => Some((x, y)),
+            // cells listed one by one needn't be in order: the entry starts at the first in reading order.
+            _ => {
+                let mut cells = Vec::new();
+                for cell in word.children("cells") {
+                    cells.extend(start(cell, "x")?.zip(start(cell, "y")?));
+                }
+                cells.into_iter().min_by_key(|&(x, y)| (y, x))
+            }
+        };
+        let number = first_cell
+            .filter(|&(x, y)| (1..=width).contains(&x) && (1..=height).contains(&y))
+            .and_then(|(x, y)| numbers.get((y - 1) * width + (x - 1)).copied().flatten())
+            .ok_or_else(|| JpzError::UnnumberedWord(id.clone()))?;
+        words.insert(id, number);
+    }
+    Ok(words)
+}
+
+fn parse_clues(clues: &Element, words: &HashMap<String, u16>) -> Result<Vec<(u16, String)>, JpzError> {
+    clues
+        .children("clue")
+        .map(|clue| {
+            let number = match clue.attr("word") {
+                Some(word) => *words.get(word).ok_or_else(|| JpzError::UnknownWord(word.to_owned()))?,
+                None => parse_attr(clue, "clue", "number")?.ok_or(JpzError::MissingElement("clue word"))?,
+            };
+            Ok((number, clue.text().trim().to_owned()))
+        })
+        .collect()
+}
+
+/// Extract the first file from a zip archive, as `.jpz` files are often zipped.
+fn unzip_first(bytes: &[u8]) -> Option<Vec<u8>> {
+    // offsets come from the file, so may point anywhere: on wasm32, even past `usize::MAX`.
+    let get = |at: usize, len: usize| bytes.get(at..at.checked_add(len)?);
+    let u16_at = |at: usize| Some(u16::from_le_bytes(get(at, 2)?.try_into().ok()?) as usize);
+    let u32_at = |at: usize| Some(u32::from_le_bytes(get(at, 4)?.try_into().ok()?) as usize);
+
+    // the end of central directory record is at the end, before a comment of up to 64k.
+    let eocd = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .take(0x10000 + 22)
+        .find(|&at| bytes[at..].starts_with(b"PK\x05\x06"))?;
+    let entries = u16_at(eocd + 10)?;
+    let mut at = u32_at(eocd + 16)?;
+
+    for _ in 0..entries {
+        if !bytes.get(at..)?.starts_with(b"PK\x01\x02") {
+            return None;
+        }
+        let method = u16_at(at + 10)?;
+        let compressed_len = u32_at(at + 20)?;
+        let uncompressed_len = u32_at(at + 24)?;
+        let name_len = u16_at(at + 28)?;
+        let skip_len = name_len + u16_at(at + 30)? + u16_at(at + 32)?;
+        let local = u32_at(at + 42)?;
+        let name = get(at + 46, name_len)?;
+        at = at.checked_add(46 + skip_len)?;
+        // skip directories and macOS resource forks.
+        if name.ends_with(b"/") || name.starts_with(b"__MACOSX") {
+            continue;
+        }
+
+        if !bytes.get(local..)?.starts_with(b"PK\x03\x04") {
+            return None;
+        }
+        let data_start = local.checked_add(30 + u16_at(local + 26)? + u16_at(local + 28)?)?;
+        let data = get(data_start, compressed_len)?;
+        // a small archive can inflate to a huge file, so trust neither it nor its stated size too far.
+        let limit = uncompressed_len.min(MAX_UNZIPPED_LEN);
+        return match method {
+            0 => Some(data.to_vec()),
+            8 => miniz_oxide::inflate::decompress_to_vec_with_limit(data, limit).ok(),
+            _ => None,
+        };
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A zip archive holding one file, stored with the given method.
+    fn zip(name: &str, method: u16, data: &[u8], uncompressed_len: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(b"PK\x03\x04\x14\x00\x00\x00");
+        out.extend(method.to_le_bytes());
+        out.extend([0; 8]); // time, date, crc (unchecked)
+        out.extend((data.len() as u32).to_le_bytes());
+        out.extend((uncompressed_len as u32).to_le_bytes());
+        out.extend((name.len() as u16).to_le_bytes());
+        out.extend([0, 0]);
+        out.extend(name.as_bytes());
+        out.extend(data);
+
+        let central = out.len();
+        out.extend(b"PK\x01\x02\x14\x00\x14\x00\x00\x00");
+        out.extend(method.to_le_bytes());
+        out.extend([0; 8]);
+        out.extend((data.len() as u32).to_le_bytes());
+        out.extend((uncompressed_len as u32).to_le_bytes());
+        out.extend((name.len() as u16).to_le_bytes());
+        out.extend([0; 12]); // extra, comment, disk, attributes
+        out.extend(0u32.to_le_bytes()); // local header offset
+        out.extend(name.as_bytes());
+        let central_len = out.len() - central;
+
+        out.extend(b"PK\x05\x06\x00\x00\x00\x00\x01\x00\x01\x00");
+        out.extend((central_len as u32).to_le_bytes());
+        out.extend((central as u32).to_le_bytes());
+        out.extend([0, 0]);
+        out
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        // text which needs escaping, or isn't ASCII.
+        xword.title = "Fish & <Chips> \"🐟\"".to_string();
+        xword.across_clues[0].1 = "Café & co.".to_string();
+        xword.markup[0].circled = true;
+        xword.markup[3].given = true;
+        let jpz = xword.to_jpz();
+        let round_trip = Crossword::from_jpz(&jpz).unwrap();
+        assert_eq!(round_trip.to_jpz(), jpz);
+        assert_eq!(round_trip.title, xword.title);
+        assert!(matches!(&round_trip.grid[1], CrosswordCell::Rebus(s) if s == "TO"));
+        assert_eq!(round_trip.across_clues, xword.across_clues);
+        assert_eq!(round_trip.down_clues, xword.down_clues);
+        assert_eq!(round_trip.markup, xword.markup);
+
+        // a clue with cells of its own, which aren't a run.
+        xword.across_details = vec![crate::ClueDetails { cells: vec![1, 0], ..Default::default() }, Default::default()];
+        let jpz = String::from_utf8(xword.to_jpz()).unwrap();
+        assert!(jpz.contains(r#"<word id="1"><cells x="2" y="1"/><cells x="1" y="1"/></word>"#), "{jpz}");
+        assert!(jpz.contains(r#"<word id="2" x="1-2" y="2"/>"#), "{jpz}");
+        let round_trip = Crossword::from_jpz(jpz.as_bytes()).unwrap();
+        assert_eq!(round_trip.across_clues, xword.across_clues);
+
+        let ipuz = include_bytes!("test_files/Ups and Downs.ipuz");
+        let mut xword = Crossword::from_ipuz(ipuz).unwrap();
+        xword.labels = xword.cell_labels();
+        xword.labels[7] = "A".to_string();
+        let round_trip = Crossword::from_jpz(&xword.to_jpz()).unwrap();
+        assert_eq!(round_trip.labels, xword.labels);
+        assert_eq!(round_trip.across_clues, xword.across_clues);
+    }
+
+    #[test]
+    fn test_parse() {
+        let jpz = r#"<?xml version="1.0" encoding="UTF-8"?>
+<crossword-compiler-applet xmlns="http://crossword.info/xml/crossword-compiler-applet">
+<rectangular-puzzle xmlns="http://crossword.info/xml/rectangular-puzzle">
+<metadata><title>Tiny &amp; Co</title><creator>Me</creator></metadata>
+<crossword>
+<grid width="3" height="2">
+<cell x="1" y="1" solution="C" number="1" background-shape="circle"/>
+<cell x="2" y="1" solution="ON" number="2"/>
+<cell x="3" y="1" solution="T" number="3"/>
+<cell x="1" y="2" solution="A"/>
+<cell x="2" y="2" type="block"/>
+<cell x="3" y="2" solution="E" hint="true"/>
+</grid>
+<word id="a1" x="1-3" y="1"/>
+<word id="d1" x="1" y="1-2"/>
+<word id="d3"><cells x="3" y="1"/><cells x="3" y="2"/></word>
+<clues><title><b>Across</b></title><clue word="a1" number="1"><i>Feline</i>, sort of</clue></clues>
+<clues><title><b>Down</b></title><clue word="d1">Regarding</clue><clue word="d3">Drink</clue></clues>
+</crossword>
+</rectangular-puzzle>
+</crossword-compiler-applet>"#;
+        let xword = Crossword::from_jpz(jpz.as_bytes()).unwrap();
+        assert_eq!(xword.title, "Tiny & Co");
+        assert_eq!(xword.author, "Me");
+        assert!(matches!(&xword.grid[1], CrosswordCell::Rebus(s) if s == "ON"));
+        assert!(xword.grid[4].is_wall());
+        assert!(xword.markup[0].circled);
+        assert!(xword.markup[5].given);
+        assert_eq!(xword.across_clues, [(1, "Feline, sort of".to_string())]);
+        assert_eq!(xword.down_clues, [(1, "Regarding".to_string()), (2, "Drink".to_string())]);
+
+        let stored = zip("puzzle.jpz", 0, jpz.as_bytes(), jpz.len());
+        assert_eq!(Crossword::from_jpz(&stored).unwrap().down_clues, xword.down_clues);
+        let deflated = miniz_oxide::deflate::compress_to_vec(jpz.as_bytes(), 6);
+        let deflated = zip("puzzle.jpz", 8, &deflated, jpz.len());
+        assert_eq!(Crossword::from_jpz(&deflated).unwrap().down_clues, xword.down_clues);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let errors = Crossword::from_jpz(b"PK\x03\x04garbage").unwrap_err().into_error_map();
+        assert!(matches!(errors["zip"], JpzError::InvalidZip));
+
+        // a zip bomb, whether or not it owns up to its size.
+        let bomb = vec![b' '; MAX_UNZIPPED_LEN + 1];
+        let deflated = miniz_oxide::deflate::compress_to_vec(&bomb, 6);
+        for stated_len in [1000, bomb.len()] {
+            let zipped = zip("puzzle.jpz", 8, &deflated, stated_len);
+            let errors = Crossword::from_jpz(&zipped).unwrap_err().into_error_map();
+            assert!(matches!(errors["zip"], JpzError::InvalidZip));
+        }
+
+        let errors = Crossword::from_jpz(b"<puzzle><crossword>").unwrap_err().into_error_map();
+        assert!(matches!(errors["xml"], JpzError::Xml(_)));
+
+        let grid = r#"<grid width="2" height="2"><cell x="1" y="1" solution="A"/><cell x="2" y="1" solution="B"/>
+<cell x="1" y="2" solution="C"/><cell x="2" y="2" solution="D"/></grid>"#;
+        let jpz = format!("<rectangular-puzzle><crossword>{}</crossword></rectangular-puzzle>", grid.replace(r#"x="2" y="2""#, r#"x="3" y="2""#));
+        let errors = Crossword::from_jpz(jpz.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(errors["grid"], JpzError::CellOutOfBounds { x: 3, y: 2 }));
+
+        let word = r#"<word id="1" x="1" y="18446744073709551615"/>"#;
+        let jpz = format!("<rectangular-puzzle><crossword>{grid}{word}</crossword></rectangular-puzzle>");
+        let errors = Crossword::from_jpz(jpz.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(&errors["words"], JpzError::UnnumberedWord(word) if word == "1"));
+
+        let jpz = format!(
+            r#"<rectangular-puzzle><crossword>{grid}<word id="1" x="1-2" y="1"/><word id="2" x="1-2" y="2"/>
+<clues><title>Across</title><clue word="1">One</clue><clue word="2">Two</clue></clues>
+<clues><title>Down</title><clue word="9">Nine</clue></clues></crossword></rectangular-puzzle>"#
+        );
+        let errors = Crossword::from_jpz(jpz.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(&errors["down_clues"], JpzError::UnknownWord(word) if word == "9"));
+
+        let jpz = jpz.replace(r#"<clue word="9">Nine</clue>"#, "");
+        let errors = Crossword::from_jpz(jpz.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(
+            errors["down_clues"],
+            JpzError::Validation(ValidationError::MismatchedClueCount { expected: 2, actual: 0 }),
+        ));
+    }
+}
@@ -0,0 +1,473 @@
+// A printable PDF: a header, the grid, and the clues flowed into columns beneath it,
+// continuing onto more pages as needed, and optionally an answer key page.
+//
+// Text is set in the standard Helvetica fonts, which every PDF reader has,
+// so we don't embed any fonts. That limits text to Windows-1252:
+// any other characters are printed as '?'.
+
+use crate::generate_svg::wrap_text;
+use crate::{Crossword, CrosswordCell};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Options for `FileFormat::Pdf`. Measurements are in points (1/72 inch).
+#[derive(Debug, Clone)]
+pub struct PdfOptions {
+    pub page_size: PageSize,
+    pub margin: f64,
+    /// Size of the clue text. Headings are scaled from this.
+    pub font_size: f64,
+    /// Add a page with the filled-in grid.
+    pub answer_key: bool,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        PdfOptions {
+            page_size: PageSize::Letter,
+            margin: 36.0,
+            font_size: 10.0,
+            answer_key: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PageSize {
+    Letter,
+    A4,
+    Custom { width: f64, height: f64 },
+}
+
+impl PageSize {
+    fn dimensions(self) -> (f64, f64) {
+        match self {
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::A4 => (595.0, 842.0),
+            PageSize::Custom { width, height } => (width, height),
+        }
+    }
+}
+
+/// Largest a grid cell will be drawn, however much room there is.
+const MAX_CELL_SIZE: f64 = 36.0;
+
+/// Helvetica's character widths, in thousandths of the font size, for ' ' through '~'.
+#[rustfmt::skip]
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn name(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+
+    fn text_width(self, text: &str, size: f64) -> f64 {
+        let width: f64 = text
+            .chars()
+            .map(|c| match c {
+                ' '..='~' => HELVETICA_WIDTHS[c as usize - ' ' as usize] as f64,
+                _ => 556.0,
+            })
+            .sum();
+        // bold is a little wider; near enough for layout.
+        let scale = match self {
+            Font::Regular => 1.0,
+            Font::Bold => 1.05,
+        };
+        width * scale * size / 1000.0
+    }
+}
+
+/// The content stream of one page, drawn with the origin at the top left.
+struct Page {
+    height: f64,
+    content: Vec<u8>,
+}
+
+impl Page {
+    fn text(&mut self, font: Font, size: f64, x: f64, y: f64, text: &str) {
+        let y = self.height - y;
+        write!(self.content, "BT /{} {size:.2} Tf {x:.2} {y:.2} Td (", font.name()).unwrap();
+        for c in text.chars() {
+            let mut buf = [0; 4];
+            let (bytes, _, had_errors) = encoding_rs::WINDOWS_1252.encode(c.encode_utf8(&mut buf));
+            let byte = if had_errors { b'?' } else { bytes[0] };
+            if matches!(byte, b'(' | b')' | b'\\') {
+                self.content.push(b'\\');
+            }
+            self.content.push(byte);
+        }
+        self.content.extend(b") Tj ET\n");
+    }
+
+    /// A filled or stroked rectangle, with `(x, y)` its top left.
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, op: &str) {
+        let y = self.height - y - height;
+        writeln!(self.content, "{x:.2} {y:.2} {width:.2} {height:.2} re {op}").unwrap();
+    }
+
+    fn line(&mut self, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) {
+        let (y1, y2) = (self.height - y1, self.height - y2);
+        writeln!(self.content, "{x1:.2} {y1:.2} m {x2:.2} {y2:.2} l S").unwrap();
+    }
+
+    fn circle(&mut self, cx: f64, cy: f64, r: f64) {
+        // four bezier curves make a good enough circle.
+        let k = 0.5523 * r;
+        let cy = self.height - cy;
+        writeln!(
+            self.content,
+            "{:.2} {cy:.2} m {:.2} {:.2} {:.2} {:.2} {cx:.2} {:.2} c {:.2} {:.2} {:.2} {:.2} {:.2} {cy:.2} c \
+             {:.2} {:.2} {:.2} {:.2} {cx:.2} {:.2} c {:.2} {:.2} {:.2} {:.2} {:.2} {cy:.2} c S",
+            cx + r,
+            cx + r, cy + k, cx + k, cy + r, cy + r,
+            cx - k, cy + r, cx - r, cy + k, cx - r,
+            cx - r, cy - k, cx - k, cy - r, cy - r,
+            cx + k, cy - r, cx + r, cy - k, cx + r,
+        )
+        .unwrap();
+    }
+}
+
+/// Lays out pages, tracking the clue columns as they fill.
+struct Layout<'a> {
+    options: &'a PdfOptions,
+    width: f64,
+    height: f64,
+    pages: Vec<Page>,
+    columns: usize,
+    column: usize,
+    /// Top of the clue columns on the current page.
+    top: f64,
+    y: f64,
+}
+
+impl Layout<'_> {
+    fn new_page(&mut self) {
+        self.pages.push(Page { height: self.height, content: Vec::new() });
+        self.top = self.options.margin;
+        self.y = self.top;
+        self.column = 0;
+    }
+
+    fn page(&mut self) -> &mut Page {
+        self.pages.last_mut().expect("layout should start with a page")
+    }
+
+    fn column_width(&self) -> f64 {
+        let gap = self.options.font_size * 1.5;
+        let content_width = self.width - self.options.margin * 2.0;
+        (content_width - gap * (self.columns - 1) as f64) / self.columns as f64
+    }
+
+    fn column_x(&self) -> f64 {
+        let gap = self.options.font_size * 1.5;
+        self.options.margin + self.column as f64 * (self.column_width() + gap)
+    }
+
+    /// Make room for a block of text `height` tall, moving to the next column or page if need be.
+    fn reserve(&mut self, height: f64) {
+        let bottom = self.height - self.options.margin;
+        if self.y + height <= bottom || self.y == self.top {
+            return;
+        }
+        self.column += 1;
+        self.y = self.top;
+        if self.column == self.columns {
+            self.new_page();
+        }
+    }
+
+    fn clues(&mut self, heading: &str, clues: &[(u16, String)], labels: &HashMap<u16, String>) {
+        let size = self.options.font_size;
+        let line_height = size * 1.2;
+        let indent = Font::Bold.text_width("000", size) + size * 0.4;
+        let max_width = self.column_width() - indent;
+        let wrapped: Vec<_> = clues
+            .iter()
+            .map(|(number, clue)| (&labels[number], wrap_text(clue, max_width, |text| Font::Regular.text_width(text, size))))
+            .collect();
+
+        // keep the heading with the first clue.
+        let first = wrapped.first().map_or(0, |(_, lines)| lines.len());
+        self.reserve(line_height * (first + 1) as f64 + size * 0.3);
+        let (x, y) = (self.column_x(), self.y + size);
+        self.page().text(Font::Bold, size * 1.2, x, y, heading);
+        self.y += line_height + size * 0.3;
+
+        for (label, lines) in wrapped {
+            self.reserve(line_height * lines.len() as f64);
+            let x = self.column_x();
+            let label_x = x + indent - size * 0.4 - Font::Bold.text_width(label, size);
+            let y = self.y + size;
+            self.page().text(Font::Bold, size, label_x, y, label);
+            for line in lines {
+                let y = self.y + size;
+                self.page().text(Font::Regular, size, x + indent, y, &line);
+                self.y += line_height;
+            }
+        }
+        self.y += line_height / 2.0;
+    }
+}
+
+impl Crossword {
+    pub(crate) fn to_pdf(&self, options: &PdfOptions) -> Vec<u8> {
+        let (width, height) = options.page_size.dimensions();
+        let size = options.font_size;
+        let content_width = width - options.margin * 2.0;
+        let mut layout = Layout {
+            options,
+            width,
+            height,
+            pages: Vec::new(),
+            columns: ((content_width / (size * 18.0)) as usize).clamp(1, 4),
+            column: 0,
+            top: 0.0,
+            y: 0.0,
+        };
+        layout.new_page();
+
+        self.header(&mut layout, &self.title);
+        let content_height = height - options.margin - layout.y;
+        let cell = (content_width / self.width as f64)
+            .min(content_height * 0.55 / self.height as f64)
+            .min(MAX_CELL_SIZE);
+        let left = options.margin + (content_width - cell * self.width as f64) / 2.0;
+        let top = layout.y;
+        self.draw_grid(layout.page(), left, top, cell, false);
+
+        layout.top = top + cell * self.height as f64 + size * 1.5;
+        layout.y = layout.top;
+        let labels = self.clue_labels();
+        layout.clues("Across", &self.across_clues, &labels);
+        layout.clues("Down", &self.down_clues, &labels);
+
+        // with no solution, there's no answer key to give.
+        if options.answer_key && self.has_solution() {
+            layout.new_page();
+            let title = if self.title.is_empty() { "Solution".to_string() } else { format!("Solution: {}", self.title) };
+            self.header(&mut layout, &title);
+            let content_height = height - options.margin - layout.y;
+            let cell = (content_width / self.width as f64)
+                .min(content_height / self.height as f64)
+                .min(MAX_CELL_SIZE);
+            let left = options.margin + (content_width - cell * self.width as f64) / 2.0;
+            let top = layout.y;
+            self.draw_grid(layout.page(), left, top, cell, true);
+        }
+
+        let pages: Vec<_> = layout.pages.into_iter().map(|page| page.content).collect();
+        assemble(&pages, width, height, &self.title, &self.author)
+    }
+
+    /// Title, author, and copyright, at the top of the page.
+    fn header(&self, layout: &mut Layout, title: &str) {
+        let size = layout.options.font_size;
+        let x = layout.options.margin;
+        let lines = [
+            (Font::Bold, size * 1.8, title),
+            (Font::Regular, size * 1.1, self.author.as_str()),
+            (Font::Regular, size * 0.8, self.copyright.as_str()),
+        ];
+        for (font, font_size, text) in lines {
+            if text.is_empty() {
+                continue;
+            }
+            layout.y += font_size;
+            let y = layout.y;
+            layout.page().text(font, font_size, x, y, text);
+            layout.y += font_size * 0.4;
+        }
+        layout.y += size;
+    }
+
+    fn draw_grid(&self, page: &mut Page, left: f64, top: f64, cell: f64, show_answers: bool) {
+        let width = self.width as usize;
+        page.content.extend(b"0.5 w\n");
+        let cells = self.grid.iter().zip(&self.markup).zip(self.cell_labels());
+        for (idx, ((fill, markup), label)) in cells.enumerate() {
+            let x = left + cell * (idx % width) as f64;
+            let y = top + cell * (idx / width) as f64;
+            match fill {
+                CrosswordCell::Wall => page.rect(x, y, cell, cell, "f"),
+                _ if markup.shaded => {
+                    page.content.extend(b"0.85 g\n");
+                    page.rect(x, y, cell, cell, "f");
+                    page.content.extend(b"0 g\n");
+                }
+                _ => (),
+            }
+            page.rect(x, y, cell, cell, "S");
+            if markup.circled {
+                page.circle(x + cell / 2.0, y + cell / 2.0, cell / 2.0 - 0.5);
+            }
+            if !label.is_empty() {
+                let size = cell * 0.3;
+                page.text(Font::Regular, size, x + cell * 0.06, y + size, &label);
+            }
+            if !(show_answers || markup.given) {
+                continue;
+            }
+            let answer = match fill {
+                CrosswordCell::Char(c) => c.to_string(),
+                CrosswordCell::Rebus(s) => s.clone(),
+                _ => continue,
+            };
+            // scale rebuses down to fit in the cell.
+            let mut size = cell * 0.6;
+            let answer_width = Font::Regular.text_width(&answer, size);
+            if answer_width > cell * 0.9 {
+                size *= cell * 0.9 / answer_width;
+            }
+            let answer_x = x + (cell - Font::Regular.text_width(&answer, size)) / 2.0;
+            page.text(Font::Regular, size, answer_x, y + cell * 0.62 + size * 0.3, &answer);
+        }
+
+        // bars go on top of every cell, so they aren't painted over by their neighbours.
+        page.content.extend(b"2.5 w\n");
+        for (idx, markup) in self.markup.iter().enumerate() {
+            let x = left + cell * (idx % width) as f64;
+            let y = top + cell * (idx / width) as f64;
+            if markup.bar_right {
+                page.line((x + cell, y), (x + cell, y + cell));
+            }
+            if markup.bar_bottom {
+                page.line((x, y + cell), (x + cell, y + cell));
+            }
+        }
+    }
+}
+
+/// A PDF text string, as UTF-16.
+fn text_string(text: &str) -> String {
+    let mut out = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        out.push_str(&format!("{unit:04X}"));
+    }
+    out.push('>');
+    out
+}
+
+/// Assemble the page content streams into a PDF document.
+fn assemble(pages: &[Vec<u8>], width: f64, height: f64, title: &str, author: &str) -> Vec<u8> {
+    // 1: catalog, 2: page tree, 3 and 4: fonts, 5: info, then each page and its contents.
+    let first_page = 6;
+    let kids: Vec<_> = (0..pages.len()).map(|idx| format!("{} 0 R", first_page + idx * 2)).collect();
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+        format!("<< /Title {} /Author {} /Producer (xword-puz) >>", text_string(title), text_string(author))
+            .into_bytes(),
+    ];
+    for (idx, content) in pages.iter().enumerate() {
+        let contents = first_page + idx * 2 + 1;
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {width} {height}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {contents} 0 R >>"
+            )
+            .into_bytes(),
+        );
+        let data = miniz_oxide::deflate::compress_to_vec_zlib(content, 6);
+        let mut stream = format!("<< /Length {} /Filter /FlateDecode >>\nstream\n", data.len()).into_bytes();
+        stream.extend(data);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::new();
+    for (idx, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        writeln!(out, "{} 0 obj", idx + 1).unwrap();
+        out.extend(object);
+        out.extend(b"\nendobj\n");
+    }
+    let xref = out.len();
+    write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).unwrap();
+    for offset in offsets {
+        writeln!(out, "{offset:010} 00000 n ").unwrap();
+    }
+    write!(
+        out,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    )
+    .unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page_count(pdf: &[u8]) -> usize {
+        pdf.windows(b"/Type /Page /".len()).filter(|w| w == b"/Type /Page /").count()
+    }
+
+    #[test]
+    fn test_structure() {
+        let xword = Crossword::from_puz(include_bytes!("test_files/encoding_oracle.puz")).unwrap();
+        let pdf = xword.to_pdf(&PdfOptions::default());
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        assert_eq!(page_count(&pdf), 1);
+
+        // every xref entry points at its object.
+        let text = String::from_utf8_lossy(&pdf);
+        let xref: usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        let entries = text[xref..].lines().skip(3).take_while(|line| line.ends_with(" n "));
+        for (idx, entry) in entries.enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj\n", idx + 1).as_bytes()));
+        }
+
+        let options = PdfOptions { answer_key: true, page_size: PageSize::A4, ..Default::default() };
+        let pdf = xword.to_pdf(&options);
+        assert_eq!(page_count(&pdf), 2);
+        assert!(text_contains(&pdf, "[0 0 595 842]"));
+    }
+
+    fn text_contains(pdf: &[u8], needle: &str) -> bool {
+        pdf.windows(needle.len()).any(|w| w == needle.as_bytes())
+    }
+
+    #[test]
+    fn test_pagination() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/encoding_oracle.puz")).unwrap();
+        let long = "a rather long clue which will certainly need to wrap onto several lines ".repeat(8);
+        for (_, clue) in xword.across_clues.iter_mut().chain(&mut xword.down_clues) {
+            *clue = long.clone();
+        }
+        let pdf = xword.to_pdf(&PdfOptions::default());
+        assert!(page_count(&pdf) > 1);
+        let large = PdfOptions { font_size: 20.0, ..Default::default() };
+        assert!(page_count(&xword.to_pdf(&large)) > page_count(&pdf));
+    }
+
+    #[test]
+    fn test_text_width() {
+        assert_eq!(Font::Regular.text_width("", 10.0), 0.0);
+        assert!((Font::Regular.text_width("Hi", 10.0) - 9.44).abs() < 1e-9);
+        assert!(Font::Regular.text_width("WWW", 10.0) > Font::Regular.text_width("iii", 10.0));
+    }
+}
@@ -1,9 +1,15 @@
-use crate::{Crossword, CrosswordCell, EncodingError};
+use crate::multi_error::MultiError;
+use crate::scramble::{SCRAMBLED_TAG, scramble_solution, unscramble_solution};
+use crate::transliterate::{describe, strip_accent, substitute};
+use crate::{
+    CellMarkup, Crossword, CrosswordArgs, CrosswordCell, EncodingError, ExportOptions, Grid, PlayerState,
+    Substitution, ValidationError, position,
+};
 use packed_struct::prelude::*;
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::iter::from_fn;
+use std::iter::{from_fn, zip};
 
 // The lifecycle for a crossword is:
 // - `CrosswordArgs`: simple, unvalidated container of named fields
@@ -15,8 +21,69 @@ use std::iter::from_fn;
 //   the `.puz` bytes.
 // - `Header::new`: generated from the `PreserializedCrossword`. These bytes are
 //   directly plopped into the start of the `.puz` file.
+//
+// Reading a `.puz` runs the same lifecycle backwards: `ParsedPuz::parse` splits the
+// bytes into the `Header`, a borrowed `PreserializedCrossword`, and any extra sections,
+// and `Crossword::from_puz` decodes those into a `Crossword`.
 
-#[derive(PackedStruct)]
+const HEADER_LEN: usize = 0x34;
+
+// Flags for each cell in the `GEXT` section.
+const GEXT_PREVIOUSLY_INCORRECT: u8 = 0x10;
+const GEXT_REVEALED: u8 = 0x40;
+const GEXT_CIRCLED: u8 = 0x80;
+
+/// `Header::scrambled_tag` for a puzzle whose solution is withheld.
+const NO_SOLUTION_TAG: u16 = 0x0002;
+
+#[derive(thiserror::Error, Debug)]
+pub enum PuzParseError {
+    #[error("file ends before the end of the {0}")]
+    Truncated(&'static str),
+    #[error("missing \"ACROSS&DOWN\" file magic")]
+    BadMagic,
+    #[error("unsupported version {0:?}")]
+    UnsupportedVersion(String),
+    #[error("could not decode {field}")]
+    DecodingError { field: String },
+    #[error("expected {expected} clues, found {actual}")]
+    MismatchedClueCount { expected: usize, actual: usize },
+    #[error("malformed {0} section")]
+    MalformedSection(&'static str),
+    #[error("solution is locked, and must be unlocked with its key")]
+    Locked,
+    #[error("solution is not locked")]
+    NotLocked,
+    #[error("{0:04} is not the key to unlock this solution")]
+    IncorrectKey(u16),
+    #[error("no key unlocks this solution")]
+    KeyNotFound,
+    #[error("invalid {field}: {error}")]
+    Invalid { field: String, error: ValidationError },
+}
+
+/// Which checksum in a `.puz` file is being referred to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checksum {
+    /// The checksum over the entire file, at the start of the header.
+    Global,
+    /// The checksum over the Crossword Information Block (dimensions and flags).
+    Cib,
+    /// One byte of the eight masked "ICHEATED" checksums.
+    Masked(usize),
+    /// The checksum of an extra section, eg `GRBS`.
+    Section([u8; 4]),
+}
+
+/// A checksum stored in a `.puz` file which does not match the file's contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumMismatch {
+    pub checksum: Checksum,
+    pub expected: u16,
+    pub actual: u16,
+}
+
+#[derive(PackedStruct, Clone)]
 #[packed_struct(endian = "lsb")]
 pub struct Header {
     checksum: u16,
@@ -105,7 +172,8 @@ impl Header {
             cksum = cksum_region(clue, cksum);
         }
 
-        if !crossword.notes.is_empty() {
+        // notes only joined the checksum in version 1.3
+        if !crossword.notes.is_empty() && crossword.version >= *b"1.3\0" {
             cksum = cksum_region(&crossword.notes, cksum);
             cksum = cksum_region(&[0], cksum);
         }
@@ -113,7 +181,7 @@ impl Header {
     }
 }
 
-fn cksum_region(base: &[u8], mut cksum: u16) -> u16 {
+pub(crate) fn cksum_region(base: &[u8], mut cksum: u16) -> u16 {
     for &byte in base {
         if cksum & 1 == 1 {
             cksum >>= 1;
@@ -126,6 +194,65 @@ fn cksum_region(base: &[u8], mut cksum: u16) -> u16 {
     cksum
 }
 
+/// `.puz` has nowhere else for this metadata, so it goes at the end of the notes
+/// as "Key: value" lines, under `NOTES_METADATA_HEADER` after a blank line.
+/// Only a trailing block under that header is read back as metadata, so notes
+/// which happen to look like metadata are left alone.
+const NOTES_METADATA: [&str; 8] = ["Date", "Editor", "Publisher", "Difficulty", "Intro", "Explanation", "URL", "Origin"];
+const NOTES_METADATA_HEADER: &str = "[Puzzle details]";
+
+impl Crossword {
+    fn puz_notes(&self) -> String {
+        let values = [
+            &self.date,
+            &self.editor,
+            &self.publisher,
+            &self.difficulty,
+            &self.intro,
+            &self.explanation,
+            &self.url,
+            &self.origin,
+        ];
+        let lines: Vec<_> = zip(NOTES_METADATA, values)
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| format!("{key}: {}", value.replace('\n', " ")))
+            .collect();
+        if lines.is_empty() {
+            return self.notes.clone();
+        }
+        let separator = if self.notes.is_empty() { "" } else { "\n\n" };
+        format!("{}{separator}{NOTES_METADATA_HEADER}\n{}", self.notes, lines.join("\n"))
+    }
+}
+
+/// Split the metadata from the end of `.puz` notes, per `NOTES_METADATA`.
+fn split_notes_metadata(notes: &str) -> (&str, HashMap<&str, &str>) {
+    let (rest, block) = notes.rsplit_once("\n\n").unwrap_or(("", notes));
+    let Some(block) = block.strip_prefix(NOTES_METADATA_HEADER).and_then(|block| block.strip_prefix('\n')) else {
+        return (notes, HashMap::new());
+    };
+    let metadata: Option<HashMap<_, _>> = block
+        .lines()
+        .map(|line| line.split_once(": ").filter(|(key, _)| NOTES_METADATA.contains(key)))
+        .collect();
+    match metadata {
+        Some(metadata) if !metadata.is_empty() => (rest, metadata),
+        _ => (notes, HashMap::new()),
+    }
+}
+
+/// The text encoding implied by the `.puz` version string.
+fn version_encoding(version: &[u8; 4]) -> Option<&'static encoding_rs::Encoding> {
+    // As near as I can tell, version 2.0 is identical to 1.x,
+    // except for the encoding.
+    // Excited to be proven wrong about this immediately ᖍ(∙⥚∙)ᖌ
+    match version {
+        [b'1', b'.', ..] => Some(encoding_rs::WINDOWS_1252),
+        [b'2', b'.', ..] => Some(encoding_rs::UTF_8),
+        _ => None,
+    }
+}
+
 /// Data about the crossword in a format that more closely matches
 /// the format used in the `.puz` file.
 struct PreserializedCrossword<'a> {
@@ -142,31 +269,32 @@ struct PreserializedCrossword<'a> {
 }
 
 impl Crossword {
-    fn preserialize(&self, version: [u8; 4]) -> Result<PreserializedCrossword<'_>, EncodingError> {
-        // As near as I can tell, version 2.0 is identical to 1.2,
-        // except for the encoding.
-        // Excited to be proven wrong about this immediately ᖍ(∙⥚∙)ᖌ
-        let encoding = match &version {
-            b"1.2\0" => encoding_rs::WINDOWS_1252,
-            b"2.0\0" => encoding_rs::UTF_8,
-            _ => panic!(),
-        };
-        let solution = self
+    fn preserialize(&self, version: [u8; 4], encoder: &mut Encoder) -> PreserializedCrossword<'_> {
+        let encoding = version_encoding(&version).expect("version should be 1.x or 2.x");
+        let solution: Vec<_> = self
             .grid
             .iter()
             .map(|cell| match cell {
                 CrosswordCell::Char(c) => *c as u8,
-                CrosswordCell::Rebus(s) => s.bytes().next().expect("rebus may not be empty"),
+                // empty rebuses are refused by `write_puz`, so this never makes it into the file.
+                CrosswordCell::Rebus(s) => s.bytes().next().unwrap_or(b'A'),
                 CrosswordCell::Wall => b'.',
-                CrosswordCell::Empty => b'A', // XXX: ???
+                // only in a puzzle with no solution, which the header says.
+                CrosswordCell::Empty => b'A',
             })
             .collect();
 
+        // The solver's entries, if any. Given cells start out filled in for the solver.
+        let entries = self.player.as_ref().map(|player| &player.entries);
         let grid = self
             .grid
             .iter()
-            .map(|cell| match cell {
-                CrosswordCell::Wall => b'.',
+            .enumerate()
+            .map(|(idx, cell)| match (cell, entries.map(|entries| &entries[idx])) {
+                (CrosswordCell::Wall, _) => b'.',
+                (_, Some(CrosswordCell::Char(c))) => *c as u8,
+                (_, Some(CrosswordCell::Rebus(s))) => s.bytes().next().unwrap_or(b'-'),
+                (CrosswordCell::Char(_) | CrosswordCell::Rebus(_), _) if self.markup[idx].given => solution[idx],
                 _ => b'-',
             })
             .collect();
@@ -179,31 +307,79 @@ impl Crossword {
             clues.iter().map(move |&(n, ref c)| (n, c.as_str(), f(n)))
         }
 
+        // encoded in file order, so the first failure is the first in the file.
+        let title = encoder.encode(encoding, &self.title, "title");
+        let author = encoder.encode(encoding, &self.author, "author");
+        let copyright = encoder.encode(encoding, &self.copyright, "copyright");
         let across = augment(&self.across_clues, |n| format!("clue {n}A"));
         let down = augment(&self.down_clues, |n| format!("clue {n}D"));
-        let clues: Result<Vec<_>, _> = merge_by(across, down, |a, d| a.0.cmp(&d.0))
-            .map(|(_, clue, field)| encode(encoding, clue, &field))
+        let clues = merge_by(across, down, |a, d| a.0.cmp(&d.0))
+            .map(|(_, clue, field)| encoder.encode(encoding, clue, &field))
             .collect();
-        let clues = clues?;
 
-        let xword = PreserializedCrossword {
+        PreserializedCrossword {
             width: self.width,
             height: self.height,
             solution,
             grid,
             clues,
-            title: encode(encoding, &self.title, "title")?,
-            author: encode(encoding, &self.author, "author")?,
-            copyright: encode(encoding, &self.copyright, "copyright")?,
-            notes: encode(encoding, &self.notes, "notes")?,
+            title,
+            author,
+            copyright,
+            notes: Cow::Owned(encoder.encode(encoding, &self.puz_notes(), "notes").into_owned()),
             version,
-        };
-        Ok(xword)
+        }
+    }
+
+    pub(crate) fn to_puz(&self, version: [u8; 4], options: &ExportOptions) -> Result<Vec<u8>, ValidationError> {
+        let mut encoder = Encoder::default();
+        let puz = self.write_puz(version, options, &mut encoder)?;
+        encoder.finish()?;
+        Ok(puz)
     }
 
-    pub(crate) fn to_puz(&self, version: [u8; 4]) -> Result<Vec<u8>, EncodingError> {
-        let this = self.preserialize(version)?;
-        let mut puz = Header::new(&this).pack().unwrap().to_vec();
+    /// Write the `.puz`, noting any text which can't be encoded in `encoder` rather than stopping.
+    pub(crate) fn write_puz(
+        &self,
+        version: [u8; 4],
+        options: &ExportOptions,
+        encoder: &mut Encoder,
+    ) -> Result<Vec<u8>, ValidationError> {
+        // problems with the puzzle itself are only reported once all of its text is encoded,
+        // so `check_encoding` hears about every field regardless.
+        let checked = self
+            .check_no_bars(".puz")
+            .and_then(|()| self.check_no_labels(".puz"))
+            .and_then(|()| self.check_rebuses());
+        let normalized;
+        let xword = if options.normalize_fill || self.has_lowercase_fill() {
+            normalized = self.normalized_puz_fill(options.normalize_fill);
+            &normalized
+        } else {
+            self
+        };
+        let checked = checked.and_then(|()| xword.check_puz_fill());
+        // the solution is either there in full, or not at all.
+        let checked = checked.and_then(|()| if xword.has_solution() { xword.check_no_empty_cells(".puz") } else { Ok(()) });
+        let encoding = version_encoding(&version).expect("version should be 1.x or 2.x");
+        let mut this = xword.preserialize(version, encoder);
+        let player_rebus_section = build_player_rebus_section(xword, encoding, encoder);
+        checked?;
+
+        let mut header = Header::new(&this);
+        if !xword.has_solution() {
+            if options.scramble_key.is_some() {
+                return Err(ValidationError::NoSolutionToScramble);
+            }
+            header.scrambled_tag = NO_SOLUTION_TAG;
+            header.generate_checksums(&this);
+        }
+        if let Some(key) = options.scramble_key {
+            header.scrambled_checksum = scramble_solution(&mut this.solution, this.width, this.height, key)?;
+            header.scrambled_tag = SCRAMBLED_TAG;
+            header.generate_checksums(&this);
+        }
+        let mut puz = header.pack().unwrap().to_vec();
         puz.extend(this.solution);
         puz.extend(this.grid);
 
@@ -215,26 +391,160 @@ impl Crossword {
             puz.extend(line.iter());
             puz.push(0);
         }
-        puz.extend(build_rebus_sections(self));
+        puz.extend(build_rebus_sections(xword));
+        puz.extend(build_timer_section(xword));
+        puz.extend(build_markup_section(xword));
+        puz.extend(player_rebus_section);
         Ok(puz)
     }
+
+    /// The solution and the solver's entries are a byte per cell, and only a few bytes are fill.
+    fn check_puz_fill(&self) -> Result<(), ValidationError> {
+        let width = self.width as usize;
+        let entries = self.player.iter().flat_map(|player| player.entries.iter().enumerate());
+        for (idx, cell) in self.grid.iter().enumerate().chain(entries) {
+            let found = match cell {
+                CrosswordCell::Char(c) => Some(*c).filter(|&c| !is_puz_fill(c)),
+                CrosswordCell::Rebus(s) => s.chars().find(|&c| !is_puz_fill(c)),
+                CrosswordCell::Wall | CrosswordCell::Empty => None,
+            };
+            if let Some(found) = found
+                && !self.grid[idx].is_wall()
+            {
+                let (row, col) = position(idx, width);
+                return Err(ValidationError::UnsupportedFill { format: ".puz", row, col, found });
+            }
+        }
+        Ok(())
+    }
+
+    /// Lowercase a-z has always been written uppercase, normalizing or not.
+    fn has_lowercase_fill(&self) -> bool {
+        let entries = self.player.iter().flat_map(|player| &player.entries);
+        self.grid.iter().chain(entries).any(|cell| match cell {
+            CrosswordCell::Char(c) => c.is_ascii_lowercase(),
+            CrosswordCell::Rebus(s) => s.chars().any(|c| c.is_ascii_lowercase()),
+            CrosswordCell::Wall | CrosswordCell::Empty => false,
+        })
+    }
+
+    /// Uppercase the fill, and with `strip_accents`, normalize it per `normalize_fill_char`.
+    fn normalized_puz_fill(&self, strip_accents: bool) -> Crossword {
+        let normalize = if strip_accents { normalize_fill_char } else { |c: char| c.to_ascii_uppercase() };
+        let mut xword = self.clone();
+        let entries = xword.player.iter_mut().flat_map(|player| &mut player.entries);
+        for cell in xword.grid.iter_mut().chain(entries) {
+            match cell {
+                CrosswordCell::Char(c) => *c = normalize(*c),
+                CrosswordCell::Rebus(s) => *s = s.chars().map(normalize).collect(),
+                CrosswordCell::Wall | CrosswordCell::Empty => (),
+            }
+        }
+        xword
+    }
+}
+
+/// Fill besides A-Z and 0-9 that Across Lite accepts.
+const PUZ_FILL_SYMBOLS: &str = "@#$%&+?";
+
+fn is_puz_fill(c: char) -> bool {
+    c.is_ascii_uppercase() || c.is_ascii_digit() || PUZ_FILL_SYMBOLS.contains(c)
 }
 
-fn encode<'a>(encoding: &'static encoding_rs::Encoding, string: &'a str, field: &str) -> Result<Cow<'a, [u8]>, EncodingError> {
+/// Uppercase `c`, and strip its accent. Characters with no single-character
+/// uppercase, like 'ß', are left alone.
+fn normalize_fill_char(c: char) -> char {
+    let c = strip_accent(c);
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) => upper,
+        _ => c,
+    }
+}
+
+pub(crate) fn encode<'a>(encoding: &'static encoding_rs::Encoding, string: &'a str, field: &str) -> Result<Cow<'a, [u8]>, EncodingError> {
     // learning three years later that encoding_rs is specifically
     // for web encoding, and thus silently encodes unrepresentable characters
     // as an html entity. It denotes that it did this with a `bool` in the
-    // return tuple, like a Go library.
+    // return tuple, like a Go library. It doesn't say where, so we go looking.
     let (out, _, failed) = encoding.encode(string);
     if failed {
-        // TODO: might be nice to use an encoding library that told you where the issue is,
-        // so you could pass that along as feedback to the user.
-        // Right now I'd rather the devil I know.
-        return Err(EncodingError { field: field.to_owned() })
+        let (offset, found) = string
+            .chars()
+            .enumerate()
+            .find(|&(_, c)| !encodes(encoding, c))
+            .expect("some character should fail to encode alone");
+        return Err(EncodingError { field: field.to_owned(), found, offset, substitute: substitute(found) });
     }
     Ok(out)
 }
 
+fn encodes(encoding: &'static encoding_rs::Encoding, c: char) -> bool {
+    let mut buf = [0; 4];
+    !encoding.encode(c.encode_utf8(&mut buf)).2
+}
+
+/// Encodes each text field, carrying on past failures to collect every field which fails.
+/// When transliterating, failures are instead replaced with a stand-in, or dropped.
+#[derive(Default)]
+pub(crate) struct Encoder {
+    errors: Vec<EncodingError>,
+    transliterate: bool,
+    pub(crate) substitutions: Vec<Substitution>,
+}
+
+impl Encoder {
+    pub(crate) fn transliterating() -> Self {
+        Encoder { transliterate: true, ..Default::default() }
+    }
+
+    /// `string` encoded, or nothing if it can't be. Only a field's first failure is kept.
+    pub(crate) fn encode<'a>(
+        &mut self,
+        encoding: &'static encoding_rs::Encoding,
+        string: &'a str,
+        field: &str,
+    ) -> Cow<'a, [u8]> {
+        match encode(encoding, string, field) {
+            Ok(out) => out,
+            Err(_) if self.transliterate => {
+                let mut text = String::new();
+                for (offset, c) in string.chars().enumerate() {
+                    if encodes(encoding, c) {
+                        text.push(c);
+                        continue;
+                    }
+                    let replacement = substitute(c).or_else(|| describe(c).map(str::to_owned)).unwrap_or_default();
+                    text.push_str(&replacement);
+                    self.substitutions.push(Substitution { field: field.to_owned(), offset, original: c, replacement });
+                }
+                let out = encode(encoding, &text, field).expect("stand-ins should be ASCII");
+                Cow::Owned(out.into_owned())
+            }
+            Err(err) => {
+                if !self.errors.iter().any(|seen| seen.field == err.field) {
+                    self.errors.push(err);
+                }
+                Cow::Borrowed(&[])
+            }
+        }
+    }
+
+    /// The first failure, as `export` reports it.
+    pub(crate) fn finish(self) -> Result<(), EncodingError> {
+        self.errors.into_iter().next().map_or(Ok(()), Err)
+    }
+
+    /// Every failure, by field.
+    pub(crate) fn finish_all(self) -> Result<(), MultiError<EncodingError>> {
+        let mut errors = MultiError::new();
+        for err in self.errors {
+            errors.insert(err.field.clone(), err);
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
 /// Merge two iterables (each sorted by `cmp`) into a single `cmp`-sorted iterator.
 ///
 /// When two elements are equal by `cmp`, prefers `a`.
@@ -289,6 +599,69 @@ fn build_rebus_sections(xword: &Crossword) -> Vec<u8> {
     out
 }
 
+fn build_timer_section(xword: &Crossword) -> Vec<u8> {
+    let Some(player) = &xword.player else {
+        return Vec::new();
+    };
+    let ltim = format!("{},{}", player.elapsed_seconds, player.paused as u8);
+    extra_section(*b"LTIM", ltim.as_bytes())
+}
+
+/// The solver's rebus entries: a null-terminated string per cell,
+/// empty for cells without a rebus entry.
+fn build_player_rebus_section(
+    xword: &Crossword,
+    encoding: &'static encoding_rs::Encoding,
+    encoder: &mut Encoder,
+) -> Vec<u8> {
+    let Some(player) = &xword.player else {
+        return Vec::new();
+    };
+    let mut rusr = Vec::new();
+    let mut any_rebus = false;
+    for (idx, entry) in player.entries.iter().enumerate() {
+        if let CrosswordCell::Rebus(s) = entry
+            && !xword.grid[idx].is_wall()
+        {
+            rusr.extend(encoder.encode(encoding, s, "player rebus").iter());
+            any_rebus = true;
+        }
+        rusr.push(0);
+    }
+
+    if !any_rebus {
+        return Vec::new();
+    }
+    extra_section(*b"RUSR", &rusr)
+}
+
+/// `.puz` has no flag for shaded or given cells, so those are left out.
+/// Given cells are still filled in for the solver, in the player grid.
+fn build_markup_section(xword: &Crossword) -> Vec<u8> {
+    let gext: Vec<u8> = xword
+        .markup
+        .iter()
+        .map(|markup| {
+            let mut flags = 0;
+            if markup.previously_incorrect {
+                flags |= GEXT_PREVIOUSLY_INCORRECT;
+            }
+            if markup.revealed {
+                flags |= GEXT_REVEALED;
+            }
+            if markup.circled {
+                flags |= GEXT_CIRCLED;
+            }
+            flags
+        })
+        .collect();
+
+    if gext.iter().all(|&flags| flags == 0) {
+        return Vec::new();
+    }
+    extra_section(*b"GEXT", &gext)
+}
+
 fn extra_section(title: [u8; 4], data: &[u8]) -> Vec<u8> {
     let len = data.len() as u16;
     let checksum = cksum_region(data, 0);
@@ -301,10 +674,437 @@ fn extra_section(title: [u8; 4], data: &[u8]) -> Vec<u8> {
     out
 }
 
+/// An extra section (eg `GRBS` or `RTBL`) following the notes.
+struct ExtraSection<'a> {
+    title: [u8; 4],
+    checksum: u16,
+    /// Offset of the checksum within the file, for repairs.
+    checksum_offset: usize,
+    data: &'a [u8],
+}
+
+/// The regions of a `.puz` file, split apart but not yet decoded.
+struct ParsedPuz<'a> {
+    header: Header,
+    body: PreserializedCrossword<'a>,
+    sections: Vec<ExtraSection<'a>>,
+}
+
+/// Cursor over the remaining bytes of a `.puz` file.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize, region: &'static str) -> Result<&'a [u8], PuzParseError> {
+        if self.bytes.len() < len {
+            return Err(PuzParseError::Truncated(region));
+        }
+        let (out, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(out)
+    }
+
+    /// Take a null-terminated string, not including the null terminator.
+    fn take_str(&mut self, region: &'static str) -> Result<&'a [u8], PuzParseError> {
+        let len = self
+            .bytes
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(PuzParseError::Truncated(region))?;
+        let out = self.take(len, region)?;
+        self.take(1, region)?;
+        Ok(out)
+    }
+
+    fn take_u16(&mut self, region: &'static str) -> Result<u16, PuzParseError> {
+        let bytes = self.take(2, region)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+}
+
+impl<'a> ParsedPuz<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, PuzParseError> {
+        let mut reader = Reader { bytes };
+        let header = reader.take(HEADER_LEN, "header")?;
+        let header = Header::unpack_from_slice(header).unwrap();
+        if &header.file_magic != b"ACROSS&DOWN\0" {
+            return Err(PuzParseError::BadMagic);
+        }
+
+        let area = header.width as usize * header.height as usize;
+        let solution = reader.take(area, "solution")?.to_vec();
+        let grid = reader.take(area, "grid")?.to_vec();
+        let title = reader.take_str("title")?;
+        let author = reader.take_str("author")?;
+        let copyright = reader.take_str("copyright")?;
+        let clues = (0..header.clue_count)
+            .map(|_| reader.take_str("clues").map(Cow::Borrowed))
+            .collect::<Result<_, _>>()?;
+        let notes = reader.take_str("notes")?;
+
+        let mut sections = Vec::new();
+        while !reader.bytes.is_empty() {
+            let title = reader.take(4, "extra section")?;
+            let len = reader.take_u16("extra section")?;
+            let checksum_offset = bytes.len() - reader.bytes.len();
+            let checksum = reader.take_u16("extra section")?;
+            let data = reader.take(len as usize, "extra section")?;
+            reader.take(1, "extra section")?;
+            sections.push(ExtraSection {
+                title: title.try_into().unwrap(),
+                checksum,
+                checksum_offset,
+                data,
+            });
+        }
+
+        let body = PreserializedCrossword {
+            width: header.width,
+            height: header.height,
+            solution,
+            grid,
+            clues,
+            title: Cow::Borrowed(title),
+            author: Cow::Borrowed(author),
+            copyright: Cow::Borrowed(copyright),
+            notes: Cow::Borrowed(notes),
+            version: header.version_string,
+        };
+        Ok(ParsedPuz { header, body, sections })
+    }
+
+    fn section(&self, title: &[u8; 4]) -> Option<&ExtraSection<'a>> {
+        self.sections.iter().find(|section| &section.title == title)
+    }
+
+    /// The header as it should be, given the contents of the file.
+    fn expected_header(&self) -> Header {
+        let mut header = self.header.clone();
+        header.generate_checksums(&self.body);
+        header
+    }
+
+    fn checksum_mismatches(&self) -> Vec<ChecksumMismatch> {
+        let expected = self.expected_header();
+        let actual = &self.header;
+        let mut mismatches = Vec::new();
+        let mut check = |checksum, expected, actual| {
+            if expected != actual {
+                mismatches.push(ChecksumMismatch { checksum, expected, actual });
+            }
+        };
+
+        check(Checksum::Global, expected.checksum, actual.checksum);
+        check(Checksum::Cib, expected.cib_checksum, actual.cib_checksum);
+        let masked = zip(expected.masked_checksums, actual.masked_checksums);
+        for (idx, (expected, actual)) in masked.enumerate() {
+            check(Checksum::Masked(idx), expected.into(), actual.into());
+        }
+        for section in &self.sections {
+            let expected = cksum_region(section.data, 0);
+            check(Checksum::Section(section.title), expected, section.checksum);
+        }
+        mismatches
+    }
+}
+
+/// Find every checksum in the `.puz` file which does not match its contents.
+///
+/// An empty list means the file's checksums are all correct.
+pub fn verify_puz_checksums(bytes: &[u8]) -> Result<Vec<ChecksumMismatch>, PuzParseError> {
+    let puz = ParsedPuz::parse(bytes)?;
+    Ok(puz.checksum_mismatches())
+}
+
+/// Rewrite every checksum in the `.puz` file to match its contents,
+/// leaving the rest of the file untouched.
+pub fn repair_puz_checksums(bytes: &[u8]) -> Result<Vec<u8>, PuzParseError> {
+    let puz = ParsedPuz::parse(bytes)?;
+    let mut out = bytes.to_vec();
+    out[..HEADER_LEN].copy_from_slice(&puz.expected_header().pack().unwrap());
+    for section in &puz.sections {
+        let checksum = cksum_region(section.data, 0).to_le_bytes();
+        out[section.checksum_offset..section.checksum_offset + 2].copy_from_slice(&checksum);
+    }
+    Ok(out)
+}
+
+impl Crossword {
+    /// Read a `Crossword` from the contents of a `.puz` file.
+    pub fn from_puz(bytes: &[u8]) -> Result<Crossword, PuzParseError> {
+        let puz = ParsedPuz::parse(bytes)?;
+        if puz.header.scrambled_tag == SCRAMBLED_TAG {
+            return Err(PuzParseError::Locked);
+        }
+        let has_solution = puz.header.scrambled_tag != NO_SOLUTION_TAG;
+        let body = &puz.body;
+        let encoding = version_encoding(&body.version).ok_or_else(|| {
+            let version = String::from_utf8_lossy(&body.version);
+            PuzParseError::UnsupportedVersion(version.trim_end_matches('\0').to_owned())
+        })?;
+
+        let mut rebuses = parse_rebus_sections(&puz, encoding)?;
+        let grid: Vec<_> = body
+            .solution
+            .iter()
+            .enumerate()
+            .map(|(idx, &byte)| match rebuses.remove(&idx) {
+                Some(rebus) => CrosswordCell::Rebus(rebus),
+                // ':' is a diagramless wall.
+                None if byte == b'.' || byte == b':' => CrosswordCell::Wall,
+                None if !has_solution => CrosswordCell::Empty,
+                None => CrosswordCell::Char(byte as char),
+            })
+            .collect();
+
+        let mut markup = parse_markup_section(&puz)?;
+        let player = parse_player_state(&puz, has_solution, encoding)?;
+        // with no progress of the solver's, the player grid is the puzzle as handed out.
+        if player.is_none() {
+            for (markup, &byte) in zip(&mut markup, &body.grid) {
+                markup.given = !matches!(byte, b'-' | b'.' | b':');
+            }
+        }
+
+        // Undo the across/down interleaving from `preserialize`.
+        let (across_nums, down_nums) = Grid {
+            width: body.width,
+            height: body.height,
+            grid: &grid,
+            markup: &[],
+        }
+        .expected_grid_nums();
+        let expected = across_nums.len() + down_nums.len();
+        if body.clues.len() != expected {
+            let actual = body.clues.len();
+            return Err(PuzParseError::MismatchedClueCount { expected, actual });
+        }
+        let across = across_nums.into_iter().map(|n| (n, 'A'));
+        let down = down_nums.into_iter().map(|n| (n, 'D'));
+        let mut across_clues = Vec::new();
+        let mut down_clues = Vec::new();
+        for ((number, direction), clue) in zip(merge_by(across, down, |a, d| a.0.cmp(&d.0)), &body.clues) {
+            let clue = decode(encoding, clue, &format!("clue {number}{direction}"))?;
+            let clues = if direction == 'A' { &mut across_clues } else { &mut down_clues };
+            clues.push((number, clue));
+        }
+
+        let notes = decode(encoding, &body.notes, "notes")?;
+        let (notes, metadata) = split_notes_metadata(&notes);
+        let metadata = |key| metadata.get(key).map_or_else(String::new, |value| value.to_string());
+        let xword = CrosswordArgs {
+            width: body.width,
+            height: body.height,
+            grid,
+            markup,
+            player,
+            across_clues,
+            down_clues,
+            title: decode(encoding, &body.title, "title")?,
+            author: decode(encoding, &body.author, "author")?,
+            copyright: decode(encoding, &body.copyright, "copyright")?,
+            notes: notes.to_owned(),
+            date: metadata("Date"),
+            editor: metadata("Editor"),
+            publisher: metadata("Publisher"),
+            difficulty: metadata("Difficulty"),
+            intro: metadata("Intro"),
+            explanation: metadata("Explanation"),
+            url: metadata("URL"),
+            origin: metadata("Origin"),
+            ..Default::default()
+        };
+        // a file can hold a puzzle we couldn't have written, eg with a reused rebus key.
+        xword.validate().map_err(|errs| {
+            let (field, error) = errs
+                .into_error_map()
+                .into_iter()
+                .min_by(|(a, _), (b, _)| a.cmp(b))
+                .expect("validation errors are never empty");
+            PuzParseError::Invalid { field: field.into_owned(), error }
+        })
+    }
+}
+
+/// Unlock a `.puz` file whose solution was scrambled with the 4-digit `key`,
+/// returning the bytes of the unlocked file.
+pub fn unlock_puz(bytes: &[u8], key: u16) -> Result<Vec<u8>, PuzParseError> {
+    let mut puz = ParsedPuz::parse(bytes)?;
+    if puz.header.scrambled_tag != SCRAMBLED_TAG {
+        return Err(PuzParseError::NotLocked);
+    }
+    let body = &mut puz.body;
+    let checksum = unscramble_solution(&mut body.solution, body.width, body.height, key);
+    if checksum != Some(puz.header.scrambled_checksum) {
+        return Err(PuzParseError::IncorrectKey(key));
+    }
+
+    puz.header.scrambled_checksum = 0;
+    puz.header.scrambled_tag = 0;
+    let mut out = bytes.to_vec();
+    out[..HEADER_LEN].copy_from_slice(&puz.expected_header().pack().unwrap());
+    let solution = &puz.body.solution;
+    out[HEADER_LEN..HEADER_LEN + solution.len()].copy_from_slice(solution);
+    Ok(out)
+}
+
+/// Find the key to a locked `.puz` file by trying all 10,000 of them.
+///
+/// The key is only checked against a 16-bit checksum, so on rare occasions
+/// a wrong key will match. Returns the lowest key which matches.
+pub fn find_puz_key(bytes: &[u8]) -> Result<u16, PuzParseError> {
+    let puz = ParsedPuz::parse(bytes)?;
+    if puz.header.scrambled_tag != SCRAMBLED_TAG {
+        return Err(PuzParseError::NotLocked);
+    }
+    let body = &puz.body;
+    (0..=9999)
+        .find(|&key| {
+            let mut solution = body.solution.clone();
+            let checksum = unscramble_solution(&mut solution, body.width, body.height, key);
+            checksum == Some(puz.header.scrambled_checksum)
+        })
+        .ok_or(PuzParseError::KeyNotFound)
+}
+
+fn decode(encoding: &'static encoding_rs::Encoding, bytes: &[u8], field: &str) -> Result<String, PuzParseError> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .map(Cow::into_owned)
+        .ok_or_else(|| PuzParseError::DecodingError { field: field.to_owned() })
+}
+
+/// Markup for each cell, from the `GEXT` section.
+fn parse_markup_section(puz: &ParsedPuz) -> Result<Vec<CellMarkup>, PuzParseError> {
+    let area = puz.body.solution.len();
+    let Some(gext) = puz.section(b"GEXT") else {
+        return Ok(vec![CellMarkup::default(); area]);
+    };
+    if gext.data.len() != area {
+        return Err(PuzParseError::MalformedSection("GEXT"));
+    }
+    let markup = gext
+        .data
+        .iter()
+        .map(|&flags| CellMarkup {
+            circled: flags & GEXT_CIRCLED != 0,
+            previously_incorrect: flags & GEXT_PREVIOUSLY_INCORRECT != 0,
+            revealed: flags & GEXT_REVEALED != 0,
+            ..Default::default()
+        })
+        .collect();
+    Ok(markup)
+}
+
+/// The solver's progress, from the player grid and the `LTIM` and `RUSR` sections.
+///
+/// `None` if the solver hasn't started the puzzle. Given cells are filled in
+/// before the solver starts, so without a timer or the solver's rebuses, any
+/// filled cells were given, as long as there's a solution to give them from.
+fn parse_player_state(
+    puz: &ParsedPuz,
+    has_solution: bool,
+    encoding: &'static encoding_rs::Encoding,
+) -> Result<Option<PlayerState>, PuzParseError> {
+    let ltim = puz.section(b"LTIM");
+    let rusr = puz.section(b"RUSR");
+    let started = puz.body.grid.iter().any(|&b| !matches!(b, b'-' | b'.' | b':'));
+    if (has_solution || !started) && ltim.is_none() && rusr.is_none() {
+        return Ok(None);
+    }
+
+    let mut entries: Vec<_> = puz
+        .body
+        .grid
+        .iter()
+        .map(|&byte| match byte {
+            b'-' => CrosswordCell::Empty,
+            b'.' | b':' => CrosswordCell::Wall,
+            _ => CrosswordCell::Char(byte as char),
+        })
+        .collect();
+
+    if let Some(rusr) = rusr {
+        let area = entries.len();
+        let rebuses: Vec<_> = rusr.data.split(|&b| b == 0).take(area).collect();
+        if rebuses.len() != area {
+            return Err(PuzParseError::MalformedSection("RUSR"));
+        }
+        for (entry, rebus) in zip(&mut entries, rebuses) {
+            if !rebus.is_empty() {
+                *entry = CrosswordCell::Rebus(decode(encoding, rebus, "RUSR")?);
+            }
+        }
+    }
+
+    // eg "42,1": seconds elapsed, and whether the timer is stopped.
+    let (elapsed_seconds, paused) = match ltim {
+        None => (0, false),
+        Some(ltim) => {
+            let timer = std::str::from_utf8(ltim.data).ok().and_then(|timer| {
+                let (elapsed, paused) = timer.split_once(',')?;
+                Some((elapsed.parse().ok()?, paused != "0"))
+            });
+            timer.ok_or(PuzParseError::MalformedSection("LTIM"))?
+        }
+    };
+
+    let player = PlayerState {
+        entries,
+        elapsed_seconds,
+        paused,
+    };
+    Ok(Some(player))
+}
+
+/// Map of cell index to rebus, from the `GRBS` and `RTBL` sections.
+fn parse_rebus_sections(
+    puz: &ParsedPuz,
+    encoding: &'static encoding_rs::Encoding,
+) -> Result<HashMap<usize, String>, PuzParseError> {
+    let Some(grbs) = puz.section(b"GRBS") else {
+        return Ok(HashMap::new());
+    };
+    let rtbl = puz
+        .section(b"RTBL")
+        .ok_or(PuzParseError::MalformedSection("RTBL"))?;
+    if grbs.data.len() != puz.body.solution.len() {
+        return Err(PuzParseError::MalformedSection("GRBS"));
+    }
+
+    // eg " 0:ON; 1:TO;"
+    let table = decode(encoding, rtbl.data, "RTBL")?;
+    let mut words = HashMap::new();
+    for entry in table.split(';').filter(|entry| !entry.trim().is_empty()) {
+        let (key, word) = entry
+            .split_once(':')
+            .filter(|(_, word)| !word.is_empty())
+            .ok_or(PuzParseError::MalformedSection("RTBL"))?;
+        let key: u8 = key
+            .trim()
+            .parse()
+            .map_err(|_| PuzParseError::MalformedSection("RTBL"))?;
+        words.insert(key, word);
+    }
+
+    grbs.data
+        .iter()
+        .enumerate()
+        .filter(|&(_, &value)| value != 0)
+        .map(|(idx, &value)| {
+            // `GRBS` values are one greater than their `RTBL` key.
+            let word = words
+                .get(&(value - 1))
+                .ok_or(PuzParseError::MalformedSection("GRBS"))?;
+            Ok((idx, word.to_string()))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::CrosswordArgs;
 
     #[test]
     fn test_smol_rebus() {
@@ -319,15 +1119,38 @@ mod tests {
             down_clues: vec![(1, "Solely".to_string()), (2, "Animated sort".to_string())],
             title: "smol".to_string(),
             author: "me".to_string(),
-            copyright: String::new(),
-            notes: String::new(),
+            ..Default::default()
         };
         let xword = xword.validate().unwrap();
-        let puz = xword.to_puz(*b"2.0\0").unwrap();
+        let puz = xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap();
 
         assert_eq!(puz, include_bytes!("test_files/smol.puz"));
     }
 
+    #[test]
+    fn test_notes_version_1_2() {
+        // before 1.3 the notes stay out of the global and masked checksums.
+        let xword = CrosswordArgs {
+            width: 2,
+            height: 2,
+            grid: "ONTO".chars().map(CrosswordCell::Char).collect(),
+            across_clues: vec![(1, "Switched on".to_string()), (3, "Toward".to_string())],
+            down_clues: vec![(1, "Overtime, briefly".to_string()), (2, "Negative".to_string())],
+            title: "Notes".to_string(),
+            author: "me".to_string(),
+            notes: "Some notes.".to_string(),
+            ..Default::default()
+        };
+        let xword = xword.validate().unwrap();
+        let puz = xword.to_puz(*b"1.2\0", &ExportOptions::default()).unwrap();
+        assert_eq!(puz, include_bytes!("test_files/notes_1.2.puz"));
+        assert_eq!(verify_puz_checksums(&puz).unwrap(), []);
+
+        let puz = xword.to_puz(*b"1.3\0", &ExportOptions::default()).unwrap();
+        assert_ne!(puz[..2], include_bytes!("test_files/notes_1.2.puz")[..2]);
+        assert_eq!(verify_puz_checksums(&puz).unwrap(), []);
+    }
+
     #[test]
     fn test_encoding_oracle() {
         let xword = CrosswordArgs {
@@ -358,9 +1181,10 @@ mod tests {
             author: "Anonymous".to_string(),
             copyright: "Copyright Anonymous, all rights reserved".to_string(),
             notes: "Created on crosshare.org".to_string(), // lol
+            ..Default::default()
         };
         let xword = xword.validate().unwrap();
-        let puz = xword.to_puz(*b"2.0\0").unwrap();
+        let puz = xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap();
 
         assert_eq!(puz, include_bytes!("test_files/encoding_oracle.puz"));
     }
@@ -379,10 +1203,372 @@ mod tests {
             down_clues: vec![(1, "Initials in cooling".to_string())],
             title: "one long".to_string(),
             author: "me".to_string(),
-            copyright: String::new(),
-            notes: String::new(),
+            ..Default::default()
         };
         let xword = xword.validate().unwrap();
-        let _puz = xword.to_puz(*b"2.0\0").unwrap();
+        let _puz = xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap();
+    }
+
+    #[test]
+    fn test_from_puz_round_trip() {
+        for puz in [&include_bytes!("test_files/smol.puz")[..], include_bytes!("test_files/encoding_oracle.puz")] {
+            let xword = Crossword::from_puz(puz).unwrap();
+            assert_eq!(xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap(), puz);
+        }
+    }
+
+    #[test]
+    fn test_from_puz_rebus() {
+        let xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        assert!(matches!(&xword.grid[1], CrosswordCell::Rebus(s) if s == "TO"));
+        assert_eq!(xword.across_clues, [(1, "Aware of".to_string()), (3, "French city".to_string())]);
+        assert_eq!(xword.down_clues, [(1, "Solely".to_string()), (2, "Animated sort".to_string())]);
+    }
+
+    #[test]
+    fn test_from_puz_truncated() {
+        let puz = include_bytes!("test_files/smol.puz");
+        let err = Crossword::from_puz(&puz[..HEADER_LEN + 5]).unwrap_err();
+        assert!(matches!(err, PuzParseError::Truncated("solution") | PuzParseError::Truncated("grid")));
+    }
+
+    #[test]
+    fn test_from_puz_invalid() {
+        // an empty word in the rebus table.
+        let mut puz = include_bytes!("test_files/smol.puz").to_vec();
+        let len = puz.len();
+        puz[len - 6..len - 1].copy_from_slice(b"2:;  ");
+        let puz = repair_puz_checksums(&puz).unwrap();
+        assert!(matches!(Crossword::from_puz(&puz), Err(PuzParseError::MalformedSection("RTBL"))));
+
+        // a revealed cell, with no solution to reveal.
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        xword.grid.fill(CrosswordCell::Empty);
+        xword.markup[0].circled = true;
+        let mut puz = xword.to_puz(*b"1.3\0", &ExportOptions::default()).unwrap();
+        let gext = puz.windows(4).position(|title| title == b"GEXT").unwrap();
+        puz[gext + 8] |= GEXT_REVEALED;
+        let puz = repair_puz_checksums(&puz).unwrap();
+        assert!(matches!(
+            Crossword::from_puz(&puz),
+            Err(PuzParseError::Invalid { field, error: ValidationError::NoSolution { row: 1, col: 1 } }) if field == "markup",
+        ));
+    }
+
+    #[test]
+    fn test_unvalidated_rebuses() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        xword.grid[1] = CrosswordCell::Rebus(String::new());
+        assert!(matches!(
+            xword.to_puz(*b"1.3\0", &ExportOptions::default()),
+            Err(ValidationError::EmptyRebus { row: 1, col: 2 }),
+        ));
+
+        let mut xword = Crossword::from_ipuz(include_bytes!("test_files/Ups and Downs.ipuz")).unwrap();
+        let letters = xword.grid.iter_mut().filter(|cell| !cell.is_wall());
+        for (idx, cell) in letters.take(100).enumerate() {
+            *cell = CrosswordCell::Rebus(format!("R{idx}"));
+        }
+        assert!(matches!(
+            xword.to_puz(*b"1.3\0", &ExportOptions::default()),
+            Err(ValidationError::TooManyRebuses(100)),
+        ));
+    }
+
+    #[test]
+    fn test_verify_checksums() {
+        let puz = include_bytes!("test_files/smol.puz");
+        assert_eq!(verify_puz_checksums(puz).unwrap(), []);
+
+        // corrupt the last letter of the RTBL section, and the global checksum.
+        let mut corrupt = puz.to_vec();
+        let len = corrupt.len();
+        corrupt[len - 3] = b'X';
+        corrupt[0] ^= 0xFF;
+        let mismatches = verify_puz_checksums(&corrupt).unwrap();
+        let checksums: Vec<_> = mismatches.iter().map(|m| m.checksum).collect();
+        assert_eq!(checksums, [Checksum::Global, Checksum::Section(*b"RTBL")]);
+
+        let repaired = repair_puz_checksums(&corrupt).unwrap();
+        assert_eq!(verify_puz_checksums(&repaired).unwrap(), []);
+        assert_eq!(repaired[len - 3], b'X');
+    }
+
+    #[test]
+    fn test_verify_checksums_masked() {
+        let mut puz = include_bytes!("test_files/encoding_oracle.puz").to_vec();
+        // first byte of the solution
+        puz[HEADER_LEN] = b'Z';
+        let mismatches = verify_puz_checksums(&puz).unwrap();
+        let checksums: Vec<_> = mismatches.iter().map(|m| m.checksum).collect();
+        // the low byte of the solution checksum happens to survive.
+        assert_eq!(checksums, [Checksum::Global, Checksum::Masked(5)]);
+        let repaired = repair_puz_checksums(&puz).unwrap();
+        assert_eq!(verify_puz_checksums(&repaired).unwrap(), []);
+    }
+
+    #[test]
+    fn test_scrambled_export() {
+        let xword = Crossword::from_puz(include_bytes!("test_files/encoding_oracle.puz")).unwrap();
+        let options = ExportOptions { scramble_key: Some(1234), ..Default::default() };
+        let puz = xword.to_puz(*b"2.0\0", &options).unwrap();
+        assert_eq!(verify_puz_checksums(&puz).unwrap(), []);
+
+        let parsed = ParsedPuz::parse(&puz).unwrap();
+        assert_eq!(parsed.header.scrambled_tag, SCRAMBLED_TAG);
+        // column-major, AABBA ANOAS ...
+        let letters = b"AABBAANOASHARTEEIEIADSSNT";
+        assert_eq!(parsed.header.scrambled_checksum, cksum_region(letters, 0));
+        assert_ne!(&parsed.body.solution[..], b"AAHEDANAISBORESBATINASEAT");
+    }
+
+    #[test]
+    fn test_unlock() {
+        let xword = Crossword::from_puz(include_bytes!("test_files/encoding_oracle.puz")).unwrap();
+        let options = ExportOptions { scramble_key: Some(1234), ..Default::default() };
+        let locked = xword.to_puz(*b"2.0\0", &options).unwrap();
+        assert!(matches!(Crossword::from_puz(&locked), Err(PuzParseError::Locked)));
+        assert!(matches!(unlock_puz(&locked, 4321), Err(PuzParseError::IncorrectKey(4321))));
+
+        let key = find_puz_key(&locked).unwrap();
+        assert_eq!(key, 1234);
+        let unlocked = unlock_puz(&locked, key).unwrap();
+        assert_eq!(unlocked, include_bytes!("test_files/encoding_oracle.puz"));
+        assert!(matches!(unlock_puz(&unlocked, key), Err(PuzParseError::NotLocked)));
+    }
+
+    #[test]
+    fn test_markup() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/encoding_oracle.puz")).unwrap();
+        xword.markup[0].circled = true;
+        xword.markup[6].shaded = true;
+        xword.markup[7].given = true;
+        xword.markup[8].previously_incorrect = true;
+        let puz = xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap();
+        assert_eq!(verify_puz_checksums(&puz).unwrap(), []);
+
+        let parsed = ParsedPuz::parse(&puz).unwrap();
+        assert_eq!(&parsed.body.grid[5..10], b"--A--");
+        let gext = parsed.section(b"GEXT").unwrap();
+        assert_eq!(gext.data[..10], [0x80, 0, 0, 0, 0, 0, 0, 0, 0x10, 0]);
+
+        // a given cell comes back as given, not as revealed, or as the solver's entry.
+        let round_trip = Crossword::from_puz(&puz).unwrap();
+        assert!(round_trip.markup[0].circled);
+        assert!(!round_trip.markup[6].shaded);
+        assert!(round_trip.markup[7].given && !round_trip.markup[7].revealed);
+        assert!(round_trip.player.is_none());
+        assert!(round_trip.markup[8].previously_incorrect);
+        let given: Vec<_> = round_trip.markup.iter().map(|markup| markup.given).collect();
+        assert_eq!(given, xword.markup.iter().map(|markup| markup.given).collect::<Vec<_>>());
+
+        // but once the solver has started, it's indistinguishable from their own entries.
+        let entries = vec![CrosswordCell::Empty; xword.grid.len()];
+        xword.player = Some(PlayerState { entries, elapsed_seconds: 5, paused: false });
+        let puz = xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap();
+        let round_trip = Crossword::from_puz(&puz).unwrap();
+        assert!(!round_trip.markup[7].given);
+        assert!(matches!(round_trip.player.unwrap().entries[7], CrosswordCell::Char('A')));
+    }
+
+    #[test]
+    fn test_player_state() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        assert!(xword.player.is_none());
+        xword.player = Some(PlayerState {
+            entries: vec![
+                CrosswordCell::Rebus("ON".to_string()), CrosswordCell::Char('T'),
+                CrosswordCell::Empty, CrosswordCell::Empty,
+            ],
+            elapsed_seconds: 42,
+            paused: true,
+        });
+        let puz = xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap();
+        assert_eq!(verify_puz_checksums(&puz).unwrap(), []);
+
+        let parsed = ParsedPuz::parse(&puz).unwrap();
+        assert_eq!(&parsed.body.grid[..], b"OT--");
+        let titles: Vec<_> = parsed.sections.iter().map(|section| &section.title).collect();
+        assert_eq!(titles, [b"GRBS", b"RTBL", b"LTIM", b"RUSR"]);
+        assert_eq!(parsed.section(b"LTIM").unwrap().data, b"42,1");
+        assert_eq!(parsed.section(b"RUSR").unwrap().data, b"ON\0\0\0\0");
+
+        let player = Crossword::from_puz(&puz).unwrap().player.unwrap();
+        assert!(matches!(&player.entries[0], CrosswordCell::Rebus(s) if s == "ON"));
+        assert!(matches!(player.entries[1], CrosswordCell::Char('T')));
+        assert!(matches!(player.entries[2], CrosswordCell::Empty));
+        assert_eq!(player.elapsed_seconds, 42);
+        assert!(player.paused);
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/encoding_oracle.puz")).unwrap();
+        xword.grid[7] = CrosswordCell::Char('É');
+        xword.grid[12] = CrosswordCell::Rebus("Sé".to_string());
+        assert!(matches!(
+            xword.to_puz(*b"2.0\0", &ExportOptions::default()),
+            Err(ValidationError::UnsupportedFill { format: ".puz", row: 2, col: 3, found: 'É' }),
+        ));
+
+        // lowercase a-z is uppercased regardless.
+        xword.grid[7] = CrosswordCell::Char('a');
+        xword.grid[12] = CrosswordCell::Rebus("se".to_string());
+        let puz = xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap();
+        let parsed = ParsedPuz::parse(&puz).unwrap();
+        assert_eq!(&parsed.body.solution[5..15], b"ANAISBOSES");
+        assert_eq!(parsed.section(b"RTBL").unwrap().data, b" 0:SE;");
+
+        xword.grid[7] = CrosswordCell::Char('É');
+        xword.grid[12] = CrosswordCell::Rebus("Sé".to_string());
+        let options = ExportOptions { normalize_fill: true, ..Default::default() };
+        let puz = xword.to_puz(*b"2.0\0", &options).unwrap();
+        let parsed = ParsedPuz::parse(&puz).unwrap();
+        assert_eq!(&parsed.body.solution[5..15], b"ANEISBOSES");
+        assert_eq!(parsed.section(b"RTBL").unwrap().data, b" 0:SE;");
+
+        // nothing to map Ω to, and no way to write it.
+        xword.grid[7] = CrosswordCell::Char('Ω');
+        assert!(matches!(
+            xword.to_puz(*b"2.0\0", &options),
+            Err(ValidationError::UnsupportedFill { row: 2, col: 3, found: 'Ω', .. }),
+        ));
+        xword.grid[7] = CrosswordCell::Char('-');
+        assert!(matches!(xword.to_puz(*b"2.0\0", &options), Err(ValidationError::UnsupportedFill { found: '-', .. })));
+    }
+
+    #[test]
+    fn test_encoding_errors() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/encoding_oracle.puz")).unwrap();
+        xword.across_clues[1].1 = "Ōsaka’s ‐ neighbor".to_string();
+        let err = xword.export(crate::FileFormat::Puz12).unwrap_err();
+        let ValidationError::EncodingError(err) = err else { panic!("expected an encoding error, got {err:?}") };
+        assert_eq!((err.field.as_str(), err.found, err.offset), ("title", '🫛', 0));
+        assert_eq!(err.substitute, None);
+
+        let errors = xword.check_encoding(crate::FileFormat::Puz12).unwrap_err().into_error_map();
+        assert_eq!(errors.len(), 2);
+        let clue = &errors["clue 6A"];
+        assert_eq!((clue.found, clue.offset, clue.substitute.as_deref()), ('Ō', 0, Some("O")));
+        assert_eq!(
+            clue.to_string(),
+            "can't encode 'Ō' (U+014C) in clue 6A, at character 0. Try \"O\" instead",
+        );
+
+        xword.title = "Test".to_string();
+        xword.across_clues[1].1 = "Osaka’s ‐ neighbor".to_string();
+        let errors = xword.check_encoding(crate::FileFormat::Puz12).unwrap_err().into_error_map();
+        let clue = &errors["clue 6A"];
+        assert_eq!((clue.found, clue.offset, clue.substitute.as_deref()), ('‐', 8, Some("-")));
+        assert!(xword.check_encoding(crate::FileFormat::Puz20).is_ok());
+
+        // fill .puz can't represent doesn't hide the text it can't encode.
+        xword.grid[7] = CrosswordCell::Char('Ω');
+        xword.markup[0].bar_right = true;
+        let errors = xword.check_encoding(crate::FileFormat::Puz12).unwrap_err().into_error_map();
+        assert_eq!(errors.keys().collect::<Vec<_>>(), ["clue 6A"]);
+        let errors = xword.check_encoding(crate::FileFormat::AcrossLiteText).unwrap_err().into_error_map();
+        let mut fields: Vec<_> = errors.keys().map(|field| field.as_ref()).collect();
+        fields.sort();
+        assert_eq!(fields, ["clue 6A", "grid row 2"]);
+        assert!(matches!(xword.export(crate::FileFormat::Puz12), Err(ValidationError::UnsupportedBars { .. })));
+    }
+
+    #[test]
+    fn test_transliterate() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/encoding_oracle.puz")).unwrap();
+        xword.across_clues[1].1 = "Ōsaka’s neighbor ❤️".to_string();
+        let (puz, substitutions) = xword.export_transliterated(crate::FileFormat::Puz12, &ExportOptions::default()).unwrap();
+        let substitution = |field: &str, offset, original, replacement: &str| Substitution {
+            field: field.to_string(),
+            offset,
+            original,
+            replacement: replacement.to_string(),
+        };
+        assert_eq!(substitutions, [
+            substitution("title", 0, '🫛', "pea pod"),
+            substitution("clue 6A", 0, 'Ō', "O"),
+            substitution("clue 6A", 17, '❤', "heart"),
+            substitution("clue 6A", 18, '\u{FE0F}', ""),
+        ]);
+        let parsed = ParsedPuz::parse(&puz).unwrap();
+        assert_eq!(&parsed.body.title[..], b"pea pod Test");
+        assert_eq!(&parsed.body.clues[6][..], b"Osaka\x92s neighbor heart");
+        assert_eq!(verify_puz_checksums(&puz).unwrap(), []);
+
+        // nothing needs replacing in utf-8.
+        let (_, substitutions) = xword.export_transliterated(crate::FileFormat::Puz20, &ExportOptions::default()).unwrap();
+        assert_eq!(substitutions, []);
+    }
+
+    #[test]
+    fn test_no_solution() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/encoding_oracle.puz")).unwrap();
+        for cell in &mut xword.grid {
+            *cell = CrosswordCell::Empty;
+        }
+        assert!(!xword.has_solution());
+        let puz = xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap();
+        assert_eq!(verify_puz_checksums(&puz).unwrap(), []);
+        let parsed = ParsedPuz::parse(&puz).unwrap();
+        assert_eq!(parsed.header.scrambled_tag, NO_SOLUTION_TAG);
+        assert!(matches!(unlock_puz(&puz, 1234), Err(PuzParseError::NotLocked)));
+
+        let mut round_trip = Crossword::from_puz(&puz).unwrap();
+        assert!(round_trip.grid.iter().all(|cell| matches!(cell, CrosswordCell::Empty)));
+
+        // but a solution missing some of its letters can't be written.
+        round_trip.grid[7] = CrosswordCell::Char('A');
+        assert!(matches!(
+            round_trip.to_puz(*b"2.0\0", &ExportOptions::default()),
+            Err(ValidationError::PartialSolution { format: ".puz", row: 1, col: 1 }),
+        ));
+        round_trip.grid[7] = CrosswordCell::Empty;
+        assert_eq!(round_trip.across_clues, xword.across_clues);
+
+        let options = ExportOptions { scramble_key: Some(1234), ..Default::default() };
+        assert!(matches!(xword.to_puz(*b"2.0\0", &options), Err(ValidationError::NoSolutionToScramble)));
+
+        // and nothing can be given away.
+        round_trip.markup[6].given = true;
+        let errors = CrosswordArgs::from(round_trip).validate().unwrap_err().into_error_map();
+        assert!(matches!(errors["markup"], ValidationError::NoSolution { row: 2, col: 2 }));
+    }
+
+    #[test]
+    fn test_notes_metadata() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        xword.notes = "Some notes.\n\nDate: is not this.".to_string();
+        xword.date = "2024-01-31".to_string();
+        xword.editor = "Ed".to_string();
+        xword.intro = "Two\nlines".to_string();
+        let puz = xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap();
+        let parsed = ParsedPuz::parse(&puz).unwrap();
+        let notes = "Some notes.\n\nDate: is not this.\n\n[Puzzle details]\nDate: 2024-01-31\nEditor: Ed\nIntro: Two lines";
+        assert_eq!(parsed.body.notes, notes.as_bytes());
+
+        let round_trip = Crossword::from_puz(&puz).unwrap();
+        assert_eq!(round_trip.notes, xword.notes);
+        assert_eq!(round_trip.date, xword.date);
+        assert_eq!(round_trip.editor, xword.editor);
+        assert_eq!(round_trip.intro, "Two lines");
+        assert_eq!(round_trip.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap(), puz);
+
+        // notes which happen to end like metadata are still just notes.
+        xword.date.clear();
+        xword.editor.clear();
+        xword.intro.clear();
+        let round_trip = Crossword::from_puz(&xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap()).unwrap();
+        assert_eq!(round_trip.notes, xword.notes);
+        assert_eq!(round_trip.date, "");
+
+        // and metadata alone needs no blank line before it.
+        xword.notes.clear();
+        xword.url = "https://example.com".to_string();
+        let puz = xword.to_puz(*b"2.0\0", &ExportOptions::default()).unwrap();
+        assert_eq!(&ParsedPuz::parse(&puz).unwrap().body.notes[..], b"[Puzzle details]\nURL: https://example.com");
+        let round_trip = Crossword::from_puz(&puz).unwrap();
+        assert_eq!(round_trip.notes, "");
+        assert_eq!(round_trip.url, xword.url);
     }
 }
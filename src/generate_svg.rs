@@ -0,0 +1,257 @@
+// Render the puzzle as an SVG image: the grid, and optionally its answers and clues.
+// There's no font metrics to be had here, so text wrapping is an estimate.
+
+use crate::xml::{escape, open_tag};
+use crate::{Crossword, CrosswordCell};
+use std::fmt::Write;
+
+/// Options for rendering a `Crossword` as an SVG, or `FileFormat::Svg`.
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    /// Width and height of each cell, in pixels.
+    pub cell_size: u32,
+    /// Font family for the grid's numbers and answers.
+    pub grid_font: String,
+    /// Font family for the clues.
+    pub clue_font: String,
+    /// Clue font size, in pixels.
+    pub clue_font_size: u32,
+    /// Fill in the solution. Given cells are always filled in.
+    pub show_answers: bool,
+    /// List the clues below the grid.
+    pub show_clues: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            cell_size: 32,
+            grid_font: "sans-serif".to_string(),
+            clue_font: "serif".to_string(),
+            clue_font_size: 14,
+            show_answers: false,
+            show_clues: false,
+        }
+    }
+}
+
+const PADDING: f64 = 4.0;
+/// Rough width of an average character, relative to the font size.
+const CHAR_WIDTH: f64 = 0.5;
+
+/// Break `text` into lines no wider than `max_width`, at spaces where possible.
+pub(crate) fn wrap_text(text: &str, max_width: f64, width: impl Fn(&str) -> f64) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && width(&format!("{line} {word}")) > max_width {
+            lines.push(std::mem::take(&mut line));
+        }
+        // a word too long for a line on its own gets broken up.
+        let mut word = word;
+        while line.is_empty() && word.chars().count() > 1 && width(word) > max_width {
+            let mut boundaries = word.char_indices().map(|(idx, _)| idx).skip(1);
+            let first = boundaries.next().unwrap_or(word.len());
+            let split = boundaries.take_while(|&idx| width(&word[..idx]) <= max_width).last().unwrap_or(first);
+            lines.push(word[..split].to_owned());
+            word = &word[split..];
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+impl Crossword {
+    pub(crate) fn to_svg(&self, options: &SvgOptions) -> String {
+        let cell = options.cell_size as f64;
+        let width = self.width as usize;
+        let grid_width = cell * self.width as f64;
+        let grid_height = cell * self.height as f64;
+
+        let mut body = String::new();
+        let grid_font = escape(&options.grid_font);
+        write!(body, "<g font-family=\"{grid_font}\" text-anchor=\"middle\">").unwrap();
+        // bars go on top of every cell, so they aren't painted over by their neighbours.
+        let mut bars = String::new();
+        let cells = self.grid.iter().zip(&self.markup).zip(self.cell_labels());
+        for (idx, ((fill, markup), label)) in cells.enumerate() {
+            let x = PADDING + cell * (idx % width) as f64;
+            let y = PADDING + cell * (idx / width) as f64;
+            let background = match (fill, &markup.color) {
+                (CrosswordCell::Wall, _) => "#000",
+                (_, Some(color)) => color,
+                _ if markup.shaded => "#dcdcdc",
+                _ => "#fff",
+            };
+            let background = escape(background);
+            write!(
+                body,
+                "<rect x=\"{x}\" y=\"{y}\" width=\"{cell}\" height=\"{cell}\" fill=\"{background}\" stroke=\"#000\"/>"
+            )
+            .unwrap();
+            for (barred, (x1, y1)) in [(markup.bar_right, (x + cell, y)), (markup.bar_bottom, (x, y + cell))] {
+                if barred {
+                    let (x2, y2) = (x + cell, y + cell);
+                    write!(bars, "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"#000\" stroke-width=\"3\"/>")
+                        .unwrap();
+                }
+            }
+            if markup.circled {
+                let r = cell / 2.0 - 1.0;
+                let (cx, cy) = (x + cell / 2.0, y + cell / 2.0);
+                write!(body, "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"{r}\" fill=\"none\" stroke=\"#000\"/>").unwrap();
+            }
+            if !label.is_empty() {
+                let size = cell * 0.3;
+                let (nx, ny) = (x + 2.0, y + size);
+                let label = escape(&label);
+                write!(body, "<text x=\"{nx}\" y=\"{ny}\" font-size=\"{size:.1}\" text-anchor=\"start\">{label}</text>")
+                    .unwrap();
+            }
+            if !(options.show_answers || markup.given) {
+                continue;
+            }
+            let answer = match fill {
+                CrosswordCell::Char(c) => c.to_string(),
+                CrosswordCell::Rebus(s) => s.clone(),
+                _ => continue,
+            };
+            // scale rebuses down to fit in the cell.
+            let len = answer.chars().count() as f64;
+            let size = (cell * 0.6).min(cell * 0.85 / (len * 0.6));
+            let (ax, ay) = (x + cell / 2.0, y + cell * 0.6 + size * 0.35);
+            let text_color = match &markup.text_color {
+                Some(color) => format!(" fill=\"{}\"", escape(color)),
+                None => String::new(),
+            };
+            write!(
+                body,
+                "<text x=\"{ax}\" y=\"{ay:.1}\" font-size=\"{size:.1}\"{text_color}>{}</text>",
+                escape(&answer)
+            )
+            .unwrap();
+        }
+        body.push_str(&bars);
+        body.push_str("</g>");
+
+        let mut total_width = grid_width + PADDING * 2.0;
+        let mut total_height = grid_height + PADDING * 2.0;
+        if options.show_clues {
+            let font_size = options.clue_font_size as f64;
+            let line_height = font_size * 1.25;
+            let column_width = (grid_width / 2.0).max(font_size * 16.0);
+            total_width = total_width.max(column_width * 2.0 + PADDING * 3.0);
+            let max_width = column_width - font_size * 2.0;
+            let measure = |text: &str| text.chars().count() as f64 * font_size * CHAR_WIDTH;
+
+            let clue_font = escape(&options.clue_font);
+            write!(body, "<g font-family=\"{clue_font}\" font-size=\"{font_size}\">").unwrap();
+            let labels = self.clue_labels();
+            let top = grid_height + PADDING * 2.0 + line_height;
+            let mut bottom = top;
+            for (column, (title, clues)) in [("Across", &self.across_clues), ("Down", &self.down_clues)]
+                .into_iter()
+                .enumerate()
+            {
+                let x = PADDING + column as f64 * (column_width + PADDING);
+                let mut y = top;
+                write!(body, "<text x=\"{x}\" y=\"{y}\" font-weight=\"bold\">{title}</text>").unwrap();
+                for (number, clue) in clues {
+                    y += line_height;
+                    let label = escape(&labels[number]);
+                    write!(body, "<text x=\"{x}\" y=\"{y}\" font-weight=\"bold\">{label}</text>").unwrap();
+                    let text_x = x + font_size * 2.0;
+                    for (i, line) in wrap_text(clue, max_width, measure).iter().enumerate() {
+                        if i > 0 {
+                            y += line_height;
+                        }
+                        write!(body, "<text x=\"{text_x}\" y=\"{y}\">{}</text>", escape(line)).unwrap();
+                    }
+                }
+                bottom = bottom.max(y);
+            }
+            body.push_str("</g>");
+            total_height = bottom + line_height / 2.0;
+        }
+
+        let mut out = String::new();
+        let (w, h) = (total_width.to_string(), total_height.ceil().to_string());
+        let view_box = format!("0 0 {w} {h}");
+        let attrs = [
+            ("xmlns", "http://www.w3.org/2000/svg"),
+            ("width", w.as_str()),
+            ("height", h.as_str()),
+            ("viewBox", view_box.as_str()),
+        ];
+        open_tag(&mut out, "svg", &attrs, false);
+        if !self.title.is_empty() {
+            write!(out, "<title>{}</title>", escape(&self.title)).unwrap();
+        }
+        writeln!(out, "<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>{body}</svg>").unwrap();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml;
+
+    #[test]
+    fn test_render() {
+        let xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        let svg = xword.to_svg(&SvgOptions::default());
+        let root = xml::parse(&svg).unwrap();
+        assert_eq!(root.name, "svg");
+        let rects = root.children("g").flat_map(|g| g.children("rect")).count();
+        assert_eq!(rects, xword.grid.len());
+        // no answers, and no clues.
+        let texts: Vec<_> = root.children("g").flat_map(|g| g.children("text")).collect();
+        let (across, down) = xword.grid().expected_grid_nums();
+        let numbers = across.iter().chain(&down).collect::<std::collections::HashSet<_>>().len();
+        assert_eq!(texts.len(), numbers);
+
+        let options = SvgOptions { show_answers: true, show_clues: true, ..Default::default() };
+        let svg = xword.to_svg(&options);
+        let root = xml::parse(&svg).unwrap();
+        let text = root.text();
+        for (_, clue) in xword.across_clues.iter().chain(&xword.down_clues) {
+            for word in clue.split_whitespace() {
+                assert!(text.contains(word), "missing {word:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_rebus() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/encoding_oracle.puz")).unwrap();
+        let idx = xword.grid.iter().position(|cell| !cell.is_wall()).unwrap();
+        xword.grid[idx] = CrosswordCell::Rebus("HEART".to_string());
+        let options = SvgOptions { show_answers: true, ..Default::default() };
+        let root = xml::parse(&xword.to_svg(&options)).unwrap();
+        let texts: Vec<_> = root.children("g").flat_map(|g| g.children("text")).collect();
+        let size = |text: &str| -> f64 {
+            let elem = texts.iter().find(|t| t.text() == text).unwrap();
+            elem.attr("font-size").unwrap().parse().unwrap()
+        };
+        let letter = xword.grid.iter().find_map(|cell| match cell {
+            CrosswordCell::Char(c) => Some(c.to_string()),
+            _ => None,
+        });
+        assert!(size("HEART") < size(&letter.unwrap()));
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        let count = |text: &str| text.chars().count() as f64;
+        assert_eq!(wrap_text("one two three four", 9.0, count), ["one two", "three", "four"]);
+        assert_eq!(wrap_text("abcdefghij", 4.0, count), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap_text("", 4.0, count), [""]);
+    }
+}
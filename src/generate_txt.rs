@@ -0,0 +1,481 @@
+// Across Lite's text format, meant for authoring puzzles by hand:
+//
+// <ACROSS PUZZLE V2>
+// <TITLE>
+//     Title
+// <AUTHOR>
+//     Author
+// <COPYRIGHT>
+//     Copyright
+// <SIZE>
+//     15x15
+// <GRID>
+//     AB1.CDE...
+// <REBUS>
+//     MARK;
+//     1:HEART:H
+// <ACROSS>
+//     One clue per line, without numbers...
+// <DOWN>
+//     ...
+// <NOTEPAD>
+//     Notes
+//
+// Like `.puz`, clue numbers are inferred from the shape of the grid.
+// Rebus cells are marked in the grid by a key character defined in <REBUS>,
+// and `MARK;` means lowercase letters in the grid are circled.
+
+use crate::generate_puz::Encoder;
+use crate::multi_error::MultiError;
+use crate::{CellMarkup, Crossword, CrosswordArgs, CrosswordCell, Direction, ValidationError, position};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::iter::zip;
+
+/// Characters which may stand in for a rebus in the grid.
+const REBUS_KEYS: &str = "1234567890@#$%&*+?!~=^<>";
+
+const SECTIONS: [&str; 9] = [
+    "TITLE", "AUTHOR", "COPYRIGHT", "SIZE", "GRID", "REBUS", "ACROSS", "DOWN", "NOTEPAD",
+];
+
+#[derive(thiserror::Error, Debug)]
+pub enum TextParseError {
+    #[error("missing <ACROSS PUZZLE> header")]
+    MissingHeader,
+    #[error("line {line}: unrecognized section <{tag}>")]
+    UnknownSection { line: usize, tag: String },
+    #[error("line {line}: expected a section, eg <TITLE>")]
+    UnexpectedLine { line: usize },
+    #[error("missing <{0}> section")]
+    MissingSection(&'static str),
+    #[error("invalid size {0:?}, expected eg \"15x15\"")]
+    InvalidSize(String),
+    #[error("grid is height {height}, but found {actual} rows")]
+    InvalidHeight { height: usize, actual: usize },
+    #[error("grid is width {width}, but row {row} is length {actual}")]
+    InvalidWidth {
+        row: usize,
+        width: usize,
+        actual: usize,
+    },
+    #[error("invalid rebus {0:?}, expected eg \"1:HEART:H\"")]
+    InvalidRebus(String),
+    #[error("{0}")]
+    Validation(#[from] ValidationError),
+}
+
+/// Accumulates the lines of the text file.
+struct TextWriter<'a> {
+    out: Vec<u8>,
+    encoder: &'a mut Encoder,
+}
+
+impl TextWriter<'_> {
+    fn section(&mut self, tag: &str) {
+        self.out.extend(format!("<{tag}>\n").bytes());
+    }
+
+    fn line(&mut self, text: &str, field: &str) {
+        self.out.push(b'\t');
+        self.out.extend(self.encoder.encode(encoding_rs::WINDOWS_1252, text, field).iter());
+        self.out.push(b'\n');
+    }
+}
+
+impl Crossword {
+    pub(crate) fn to_across_lite_text(&self) -> Result<Vec<u8>, ValidationError> {
+        let mut encoder = Encoder::default();
+        let text = self.write_across_lite_text(&mut encoder)?;
+        encoder.finish()?;
+        Ok(text)
+    }
+
+    /// Write the text file, noting any text which can't be encoded in `encoder` rather than stopping.
+    pub(crate) fn write_across_lite_text(&self, encoder: &mut Encoder) -> Result<Vec<u8>, ValidationError> {
+        // problems with the puzzle itself are only reported once all of its text is encoded,
+        // so `check_encoding` hears about every field regardless.
+        let mut checked = self
+            .check_no_bars("Across Lite text")
+            .and_then(|()| self.check_no_labels("Across Lite text"))
+            .and_then(|()| self.check_no_empty_cells("Across Lite text"))
+            .and_then(|()| self.check_circled_letters())
+            .and_then(|()| self.check_no_empty_clues());
+        let mut rebus_keys: HashMap<&str, char> = HashMap::new();
+        let mut rebuses = Vec::new();
+        for cell in &self.grid {
+            if let CrosswordCell::Rebus(s) = cell
+                && !rebus_keys.contains_key(s.as_str())
+            {
+                rebus_keys.insert(s, ' ');
+                rebuses.push(s);
+            }
+        }
+        // a key which is also fill would read back as a rebus.
+        let keys: Vec<char> = REBUS_KEYS
+            .chars()
+            .filter(|key| !self.grid.iter().any(|cell| matches!(cell, CrosswordCell::Char(c) if c == key)))
+            .collect();
+        if rebuses.len() > keys.len() {
+            checked = checked.and(Err(ValidationError::TooManyRebusesForFormat {
+                format: "Across Lite text",
+                limit: keys.len(),
+                actual: rebuses.len(),
+            }));
+        }
+        let rebuses: Vec<_> = zip(keys, rebuses)
+            .map(|(key, rebus)| {
+                rebus_keys.insert(rebus, key);
+                let short = rebus.chars().next().expect("rebus may not be empty");
+                format!("{key}:{rebus}:{short}")
+            })
+            .collect();
+        let mark = self.markup.iter().any(|markup| markup.circled);
+
+        let mut text = TextWriter { out: b"<ACROSS PUZZLE V2>\n".to_vec(), encoder };
+        text.section("TITLE");
+        text.line(&self.title, "title");
+        text.section("AUTHOR");
+        text.line(&self.author, "author");
+        text.section("COPYRIGHT");
+        text.line(&self.copyright, "copyright");
+        text.section("SIZE");
+        text.line(&format!("{}x{}", self.width, self.height), "size");
+
+        text.section("GRID");
+        let width = self.width as usize;
+        for (row_idx, (row, markup)) in zip(self.grid.chunks(width), self.markup.chunks(width)).enumerate() {
+            let line: String = zip(row, markup)
+                .map(|(cell, markup)| {
+                    let c = match cell {
+                        CrosswordCell::Wall => '.',
+                        // refused above, so this never makes it into the file.
+                        CrosswordCell::Empty => '-',
+                        CrosswordCell::Char(c) => *c,
+                        CrosswordCell::Rebus(s) => rebus_keys[s.as_str()],
+                    };
+                    if markup.circled { c.to_ascii_lowercase() } else { c }
+                })
+                .collect();
+            text.line(&line, &format!("grid row {}", row_idx + 1));
+        }
+
+        if mark || !rebuses.is_empty() {
+            text.section("REBUS");
+            if mark {
+                text.line("MARK;", "rebus");
+            }
+            for rebus in &rebuses {
+                text.line(rebus, "rebus");
+            }
+        }
+
+        text.section("ACROSS");
+        for (n, clue) in &self.across_clues {
+            text.line(&clue.replace('\n', " "), &format!("clue {n}A"));
+        }
+        text.section("DOWN");
+        for (n, clue) in &self.down_clues {
+            text.line(&clue.replace('\n', " "), &format!("clue {n}D"));
+        }
+
+        if !self.notes.is_empty() {
+            text.section("NOTEPAD");
+            for (idx, line) in self.notes.lines().enumerate() {
+                text.line(line, &format!("notes line {}", idx + 1));
+            }
+        }
+        checked?;
+        Ok(text.out)
+    }
+
+    /// Circled cells are marked by lowercasing, which only letters have.
+    fn check_circled_letters(&self) -> Result<(), ValidationError> {
+        let width = self.width as usize;
+        let unmarkable = zip(&self.grid, &self.markup)
+            .position(|(cell, markup)| markup.circled && !matches!(cell, CrosswordCell::Char(c) if c.is_ascii_alphabetic()));
+        if let Some(idx) = unmarkable {
+            let (row, col) = position(idx, width);
+            return Err(ValidationError::UnsupportedCell { format: "Across Lite text", row, col });
+        }
+        Ok(())
+    }
+
+    /// Clues are a line each, and blank lines are skipped, so an empty clue would go missing.
+    fn check_no_empty_clues(&self) -> Result<(), ValidationError> {
+        for (direction, clues) in [(Direction::Across, &self.across_clues), (Direction::Down, &self.down_clues)] {
+            if let Some(&(number, _)) = clues.iter().find(|(_, clue)| clue.trim().is_empty()) {
+                return Err(ValidationError::EmptyClue { format: "Across Lite text", number, direction });
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a `Crossword` from the contents of an Across Lite text (`.txt`) file.
+    pub fn from_across_lite_text(bytes: &[u8]) -> Result<Crossword, MultiError<TextParseError>> {
+        // Across Lite writes these in the system codepage, but we may as well
+        // accept utf-8 from more modern tools.
+        let text = match std::str::from_utf8(bytes) {
+            Ok(text) => Cow::Borrowed(text),
+            Err(_) => encoding_rs::WINDOWS_1252.decode_without_bom_handling(bytes).0,
+        };
+        let sections = split_sections(text.trim_start_matches('\u{feff}'))
+            .map_err(|err| MultiError::from([("text", err)]))?;
+
+        let section = |tag: &'static str| -> Result<&Vec<&str>, MultiError<TextParseError>> {
+            sections
+                .get(tag)
+                .ok_or_else(|| MultiError::from([(tag, TextParseError::MissingSection(tag))]))
+        };
+        let joined = |tag| sections.get(tag).map(|lines: &Vec<&str>| lines.join(" ")).unwrap_or_default();
+
+        let size = section("SIZE")?.join("");
+        let (width, height) = size
+            .split_once(['x', 'X'])
+            .and_then(|(w, h)| Some((w.trim().parse::<u8>().ok()?, h.trim().parse::<u8>().ok()?)))
+            .ok_or_else(|| MultiError::from([("size", TextParseError::InvalidSize(size.clone()))]))?;
+
+        let (mark, rebuses) = parse_rebuses(sections.get("REBUS").map_or(&[], |lines| &lines[..]))
+            .map_err(|err| MultiError::from([("rebus", err)]))?;
+        let (grid, markup) = parse_grid(section("GRID")?, width, height, mark, &rebuses)
+            .map_err(|err| MultiError::from([("grid", err)]))?;
+
+        let args = CrosswordArgs {
+            width,
+            height,
+            grid,
+            markup,
+            title: joined("TITLE"),
+            author: joined("AUTHOR"),
+            copyright: joined("COPYRIGHT"),
+            notes: sections.get("NOTEPAD").map(|lines| lines.join("\n")).unwrap_or_default(),
+            ..Default::default()
+        };
+        let (across, down) = args.grid().expected_grid_nums();
+        let across_clues = number_clues(&across, section("ACROSS")?);
+        let down_clues = number_clues(&down, section("DOWN")?);
+        let args = CrosswordArgs { across_clues, down_clues, ..args };
+        args.validate().map_err(|errs| errs.map(TextParseError::from))
+    }
+}
+
+/// Map of section tag to its lines, trimmed. Blank lines are dropped,
+/// except from the middle of the <NOTEPAD>.
+fn split_sections(text: &str) -> Result<HashMap<&str, Vec<&str>>, TextParseError> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .skip_while(|(_, line)| line.is_empty());
+    match lines.next() {
+        Some((_, "<ACROSS PUZZLE>" | "<ACROSS PUZZLE V2>")) => (),
+        _ => return Err(TextParseError::MissingHeader),
+    }
+
+    let mut sections: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut current = None;
+    for (idx, line) in lines {
+        if let Some(tag) = line.strip_prefix('<').and_then(|line| line.strip_suffix('>')) {
+            if !SECTIONS.contains(&tag) {
+                return Err(TextParseError::UnknownSection { line: idx + 1, tag: tag.to_owned() });
+            }
+            sections.insert(tag, Vec::new());
+            current = Some(tag);
+            continue;
+        }
+        match current {
+            Some("NOTEPAD") => sections.get_mut("NOTEPAD").unwrap().push(line),
+            _ if line.is_empty() => (),
+            Some(tag) => sections.get_mut(tag).unwrap().push(line),
+            None => return Err(TextParseError::UnexpectedLine { line: idx + 1 }),
+        }
+    }
+    if let Some(notes) = sections.get_mut("NOTEPAD") {
+        while notes.last().is_some_and(|line| line.is_empty()) {
+            notes.pop();
+        }
+    }
+    Ok(sections)
+}
+
+/// Whether `MARK;` was specified, and the map of rebus keys to rebuses.
+fn parse_rebuses<'a>(lines: &[&'a str]) -> Result<(bool, HashMap<char, &'a str>), TextParseError> {
+    let mut mark = false;
+    let mut rebuses = HashMap::new();
+    for &line in lines {
+        if line.eq_ignore_ascii_case("MARK;") {
+            mark = true;
+            continue;
+        }
+        let mut parts = line.splitn(3, ':');
+        let (Some(key), Some(rebus), Some(_short)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(TextParseError::InvalidRebus(line.to_owned()));
+        };
+        let mut key_chars = key.chars();
+        let (Some(key), None) = (key_chars.next(), key_chars.next()) else {
+            return Err(TextParseError::InvalidRebus(line.to_owned()));
+        };
+        if rebus.is_empty() {
+            return Err(TextParseError::InvalidRebus(line.to_owned()));
+        }
+        rebuses.insert(key, rebus);
+    }
+    Ok((mark, rebuses))
+}
+
+fn parse_grid(
+    rows: &[&str],
+    width: u8,
+    height: u8,
+    mark: bool,
+    rebuses: &HashMap<char, &str>,
+) -> Result<(Vec<CrosswordCell>, Vec<CellMarkup>), TextParseError> {
+    let width = width as usize;
+    let height = height as usize;
+    if rows.len() != height {
+        return Err(TextParseError::InvalidHeight { height, actual: rows.len() });
+    }
+    let mut grid = Vec::new();
+    let mut markup = Vec::new();
+    for (row, line) in rows.iter().enumerate() {
+        let actual = line.chars().count();
+        if actual != width {
+            return Err(TextParseError::InvalidWidth { row: row + 1, width, actual });
+        }
+        for c in line.chars() {
+            let cell = match c {
+                '.' => CrosswordCell::Wall,
+                c if rebuses.contains_key(&c) => CrosswordCell::Rebus(rebuses[&c].to_owned()),
+                c => CrosswordCell::Char(c.to_ascii_uppercase()),
+            };
+            grid.push(cell);
+            markup.push(CellMarkup {
+                circled: mark && c.is_ascii_lowercase(),
+                ..Default::default()
+            });
+        }
+    }
+    Ok((grid, markup))
+}
+
+/// Clues in the text format are unnumbered, so number them in grid order.
+/// Any clues beyond those the grid expects are numbered past the end,
+/// for `validate` to complain about.
+fn number_clues(expected: &[u16], clues: &[&str]) -> Vec<(u16, String)> {
+    let last = expected.last().copied().unwrap_or(0);
+    let numbers = expected.iter().copied().chain(last + 1..);
+    zip(numbers, clues).map(|(n, clue)| (n, clue.to_string())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        xword.grid[0] = CrosswordCell::Char('A');
+        xword.markup[0].circled = true;
+        xword.across_clues[0].1 = "Café, say".to_string();
+        xword.notes = "line one\n\nline three".to_string();
+        let text = xword.to_across_lite_text().unwrap();
+        let round_trip = Crossword::from_across_lite_text(&text).unwrap();
+        assert_eq!(round_trip.to_across_lite_text().unwrap(), text);
+        assert!(matches!(round_trip.grid[0], CrosswordCell::Char('A')));
+        assert_eq!(round_trip.markup, xword.markup);
+        assert_eq!(round_trip.across_clues, xword.across_clues);
+        assert_eq!(round_trip.notes, xword.notes);
+
+        // blank lines are skipped, so an empty clue can't be written.
+        let mut empty_clue = xword.clone();
+        empty_clue.down_clues[1].1 = " ".to_string();
+        assert!(matches!(
+            empty_clue.to_across_lite_text(),
+            Err(ValidationError::EmptyClue { format: "Across Lite text", number: 2, direction: Direction::Down }),
+        ));
+
+        // circles are marked by lowercasing, which rebus keys don't have.
+        xword.markup[1].circled = true;
+        assert!(matches!(
+            xword.to_across_lite_text(),
+            Err(ValidationError::UnsupportedCell { format: "Across Lite text", row: 1, col: 2 }),
+        ));
+    }
+
+    #[test]
+    fn test_rebus_keys() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        xword.grid[0] = CrosswordCell::Char('1');
+        xword.grid[3] = CrosswordCell::Char('2');
+        let text = xword.to_across_lite_text().unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("<GRID>\n\t13\n\t42\n"), "{text}");
+        assert!(text.contains("\t3:TO:T\n\t4:LY:L\n"), "{text}");
+
+        let round_trip = Crossword::from_across_lite_text(text.as_bytes()).unwrap();
+        assert!(matches!(round_trip.grid[0], CrosswordCell::Char('1')));
+        assert!(matches!(&round_trip.grid[1], CrosswordCell::Rebus(s) if s == "TO"));
+        assert!(matches!(&round_trip.grid[2], CrosswordCell::Rebus(s) if s == "LY"));
+        assert!(matches!(round_trip.grid[3], CrosswordCell::Char('2')));
+    }
+
+    #[test]
+    fn test_empty_cells() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        xword.grid[2] = CrosswordCell::Empty;
+        assert!(matches!(
+            xword.to_across_lite_text(),
+            Err(ValidationError::PartialSolution { format: "Across Lite text", row: 2, col: 1 }),
+        ));
+    }
+
+    #[test]
+    fn test_parse() {
+        let text = "\
+<ACROSS PUZZLE V2>
+<TITLE>
+\tTiny
+<SIZE>
+\t3x2
+<GRID>
+\tc1T
+\tA.e
+<REBUS>
+\tMARK;
+\t1:ON:O
+<ACROSS>
+\tFeline, sort of
+
+<DOWN>
+\tRegarding
+\tDrink
+<NOTEPAD>
+\tline one
+
+\tline three
+";
+        let xword = Crossword::from_across_lite_text(text.as_bytes()).unwrap();
+        assert_eq!(xword.title, "Tiny");
+        assert!(matches!(&xword.grid[1], CrosswordCell::Rebus(s) if s == "ON"));
+        assert!(matches!(xword.grid[5], CrosswordCell::Char('E')));
+        let circled: Vec<_> = xword.markup.iter().map(|m| m.circled).collect();
+        assert_eq!(circled, [true, false, false, false, false, true]);
+        assert_eq!(xword.across_clues, [(1, "Feline, sort of".to_string())]);
+        assert_eq!(xword.down_clues, [(1, "Regarding".to_string()), (2, "Drink".to_string())]);
+        assert_eq!(xword.notes, "line one\n\nline three");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let errors = Crossword::from_across_lite_text(b"<TITLE>\n").unwrap_err().into_error_map();
+        assert!(matches!(errors["text"], TextParseError::MissingHeader));
+
+        let text = "<ACROSS PUZZLE>\n<SIZE>\n2x2\n<GRID>\nAB\nCD\n<ACROSS>\nOne\n<DOWN>\nOne\nTwo\n";
+        let errors = Crossword::from_across_lite_text(text.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(
+            errors["across_clues"],
+            TextParseError::Validation(ValidationError::MismatchedClueCount { expected: 2, actual: 1 }),
+        ));
+        assert!(!errors.contains_key("down_clues"));
+    }
+}
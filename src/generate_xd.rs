@@ -0,0 +1,437 @@
+// The `.xd` format, a plain utf-8 format meant to diff well and be edited by hand:
+//
+// Title: Title
+// Author: Author
+// Editor: Editor
+// Publisher: Publisher
+// Copyright: Copyright
+// Date: 2024-01-31
+// Rebus: 1=HEART 2=ON
+// Special: circle
+//
+//
+// AB1#CDE
+// ...
+//
+//
+// A1. Clue ~ ANSWER
+// ...
+//
+// D1. Clue ~ ANSWER
+// ...
+//
+//
+// Notes, free-form.
+//
+// Sections are separated by blank lines. `#` is a block, rebus cells are
+// marked in the grid by a key defined in the `Rebus` header, and if there's a
+// `Special` header, lowercase letters in the grid are circled (or shaded).
+
+use crate::multi_error::MultiError;
+use crate::{CellMarkup, Crossword, CrosswordArgs, CrosswordCell, Direction, ValidationError};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::iter::zip;
+
+/// Characters which may stand in for a rebus in the grid.
+const REBUS_KEYS: &str = "1234567890@$%&*+?!~=^<>";
+
+#[derive(thiserror::Error, Debug)]
+pub enum XdParseError {
+    #[error("missing grid")]
+    MissingGrid,
+    #[error("line {line}: expected a header, eg \"Title: Title\"")]
+    InvalidHeader { line: usize },
+    #[error("invalid rebus {0:?}, expected eg \"1=HEART\"")]
+    InvalidRebus(String),
+    #[error("grid is too large ({width}x{height})")]
+    InvalidSize { width: usize, height: usize },
+    #[error("grid is width {width}, but row {row} is length {actual}")]
+    InvalidWidth {
+        row: usize,
+        width: usize,
+        actual: usize,
+    },
+    #[error("line {line}: expected a clue, eg \"A1. Clue ~ ANSWER\"")]
+    InvalidClue { line: usize },
+    #[error("{0}")]
+    Validation(#[from] ValidationError),
+}
+
+impl Crossword {
+    pub(crate) fn to_xd(&self) -> Result<Vec<u8>, ValidationError> {
+        self.check_no_bars("xd")?;
+        self.check_no_labels("xd")?;
+        let keys = REBUS_KEYS.chars().filter(|key| {
+            !self.grid.iter().any(|cell| matches!(cell, CrosswordCell::Char(c) if c == key))
+        });
+        // single characters which would be mistaken for something else go in the rebus legend too.
+        let needs_key = |cell: &CrosswordCell| match cell {
+            CrosswordCell::Rebus(s) => Some(s.clone()),
+            CrosswordCell::Char(c) if matches!(c, '#' | '.' | '_') || c.is_lowercase() => Some(c.to_string()),
+            _ => None,
+        };
+        let mut rebuses: Vec<String> = Vec::new();
+        for cell in &self.grid {
+            if let Some(rebus) = needs_key(cell)
+                && !rebuses.contains(&rebus)
+            {
+                rebuses.push(rebus);
+            }
+        }
+        let keys: Vec<char> = keys.collect();
+        if rebuses.len() > keys.len() {
+            return Err(ValidationError::TooManyRebusesForFormat {
+                format: "xd",
+                limit: keys.len(),
+                actual: rebuses.len(),
+            });
+        }
+        let rebus_keys: HashMap<&str, char> = zip(&rebuses, &keys).map(|(rebus, &key)| (rebus.as_str(), key)).collect();
+
+        let mut out = String::new();
+        let headers = [
+            ("Title", &self.title),
+            ("Author", &self.author),
+            ("Editor", &self.editor),
+            ("Publisher", &self.publisher),
+            ("Copyright", &self.copyright),
+            ("Date", &self.date),
+        ];
+        for (key, value) in headers {
+            if !value.is_empty() {
+                writeln!(out, "{key}: {}", value.replace('\n', " ")).unwrap();
+            }
+        }
+        if !rebuses.is_empty() {
+            let legend: Vec<_> = zip(&keys, &rebuses).map(|(key, rebus)| format!("{key}={rebus}")).collect();
+            writeln!(out, "Rebus: {}", legend.join(" ")).unwrap();
+        }
+        let circled = self.markup.iter().any(|markup| markup.circled);
+        let shaded = self.markup.iter().any(|markup| markup.shaded);
+        // only one kind of special cell: prefer circles, like `.puz`.
+        let special = |markup: &CellMarkup| if circled { markup.circled } else { markup.shaded };
+        if circled || shaded {
+            writeln!(out, "Special: {}", if circled { "circle" } else { "shaded" }).unwrap();
+        }
+
+        out.push_str("\n\n");
+        let width = self.width as usize;
+        for (row, (cells, markup)) in zip(self.grid.chunks(width), self.markup.chunks(width)).enumerate() {
+            for (col, (cell, markup)) in zip(cells, markup).enumerate() {
+                let c = match needs_key(cell) {
+                    Some(rebus) => rebus_keys[rebus.as_str()],
+                    None => match cell {
+                        CrosswordCell::Wall => '#',
+                        CrosswordCell::Empty => '.',
+                        CrosswordCell::Char(c) => *c,
+                        CrosswordCell::Rebus(_) => unreachable!("rebuses always need a key"),
+                    },
+                };
+                if !special(markup) || cell.is_wall() {
+                    out.push(c);
+                    continue;
+                }
+                // special cells are marked by lowercasing, which only A-Z have.
+                if !c.is_ascii_uppercase() {
+                    return Err(ValidationError::UnsupportedCell { format: "xd", row: row + 1, col: col + 1 });
+                }
+                out.push(c.to_ascii_lowercase());
+            }
+            out.push('\n');
+        }
+
+        let entries = self.entries();
+        for (direction, clues) in [(Direction::Across, &self.across_clues), (Direction::Down, &self.down_clues)] {
+            out.push_str(if direction == Direction::Across { "\n\n" } else { "\n" });
+            let answers: HashMap<u16, String> = entries
+                .iter()
+                .filter(|entry| entry.direction == direction)
+                .map(|entry| {
+                    let answer = entry
+                        .cells
+                        .iter()
+                        .map(|&(row, col)| match &self.grid[row * width + col] {
+                            CrosswordCell::Char(c) => c.to_string(),
+                            CrosswordCell::Rebus(s) => s.clone(),
+                            _ => ".".to_string(),
+                        })
+                        .collect();
+                    (entry.number, answer)
+                })
+                .collect();
+            let direction = if direction == Direction::Across { 'A' } else { 'D' };
+            for (number, clue) in clues {
+                let clue = clue.replace('\n', " ");
+                writeln!(out, "{direction}{number}. {clue} ~ {}", answers[number]).unwrap();
+            }
+        }
+
+        if !self.notes.is_empty() {
+            write!(out, "\n\n{}\n", self.notes).unwrap();
+        }
+        Ok(out.into_bytes())
+    }
+
+    /// Read a `Crossword` from the contents of an `.xd` file.
+    pub fn from_xd(bytes: &[u8]) -> Result<Crossword, MultiError<XdParseError>> {
+        let text = String::from_utf8_lossy(bytes);
+        let lines: Vec<&str> = text.trim_start_matches('\u{feff}').lines().map(str::trim_end).collect();
+        let mut blocks = split_blocks(&lines).into_iter().peekable();
+
+        let mut headers = HashMap::new();
+        if let Some(&(start, end)) = blocks.peek()
+            && lines[start].contains(':')
+        {
+            blocks.next();
+            for (line, text) in zip(start.., &lines[start..end]) {
+                let (key, value) = text
+                    .split_once(':')
+                    .ok_or_else(|| MultiError::from([("header", XdParseError::InvalidHeader { line: line + 1 })]))?;
+                headers.insert(key.trim().to_ascii_lowercase(), value.trim());
+            }
+        }
+        let header = |key: &str| headers.get(key).copied().unwrap_or("");
+
+        let rebuses = parse_rebuses(header("rebus")).map_err(|err| MultiError::from([("header", err)]))?;
+        let special = !header("special").is_empty();
+        let shaded = header("special").eq_ignore_ascii_case("shaded");
+        let (start, end) = blocks.next().ok_or_else(|| MultiError::from([("grid", XdParseError::MissingGrid)]))?;
+        let (width, height, grid, markup) = parse_grid(&lines[start..end], special, shaded, &rebuses)
+            .map_err(|err| MultiError::from([("grid", err)]))?;
+
+        let mut across_clues = Vec::new();
+        let mut down_clues = Vec::new();
+        let mut notes = String::new();
+        while let Some((start, end)) = blocks.next() {
+            if parse_clue(lines[start]).is_none() {
+                // everything from here on is notes, blank lines and all.
+                let end = blocks.last().map_or(end, |(_, end)| end);
+                notes = lines[start..end].join("\n");
+                break;
+            }
+            for (line, text) in zip(start.., &lines[start..end]) {
+                let (direction, number, clue) = parse_clue(text)
+                    .ok_or_else(|| MultiError::from([("clues", XdParseError::InvalidClue { line: line + 1 })]))?;
+                let clues = if direction == 'A' { &mut across_clues } else { &mut down_clues };
+                clues.push((number, clue.to_owned()));
+            }
+        }
+        if notes.is_empty() {
+            notes = header("notes").to_owned();
+        }
+
+        let args = CrosswordArgs {
+            width,
+            height,
+            grid,
+            markup,
+            across_clues,
+            down_clues,
+            title: header("title").to_owned(),
+            author: header("author").to_owned(),
+            copyright: header("copyright").to_owned(),
+            notes,
+            date: header("date").to_owned(),
+            editor: header("editor").to_owned(),
+            publisher: header("publisher").to_owned(),
+            ..Default::default()
+        };
+        args.validate().map_err(|errs| errs.map(XdParseError::from))
+    }
+}
+
+/// The `start..end` line ranges of each run of non-blank lines.
+fn split_blocks(lines: &[&str]) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::new();
+    let mut start = None;
+    for (idx, line) in lines.iter().enumerate() {
+        match (start, line.is_empty()) {
+            (None, false) => start = Some(idx),
+            (Some(s), true) => {
+                blocks.push((s, idx));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(s) = start {
+        blocks.push((s, lines.len()));
+    }
+    blocks
+}
+
+fn parse_rebuses(legend: &str) -> Result<HashMap<char, &str>, XdParseError> {
+    let mut rebuses = HashMap::new();
+    for entry in legend.split_whitespace() {
+        let invalid = || XdParseError::InvalidRebus(entry.to_owned());
+        let (key, rebus) = entry.split_once('=').ok_or_else(invalid)?;
+        let mut key_chars = key.chars();
+        let (Some(key), None) = (key_chars.next(), key_chars.next()) else {
+            return Err(invalid());
+        };
+        if rebus.is_empty() {
+            return Err(invalid());
+        }
+        rebuses.insert(key, rebus);
+    }
+    Ok(rebuses)
+}
+
+#[allow(clippy::type_complexity)]
+fn parse_grid(
+    rows: &[&str],
+    special: bool,
+    shaded: bool,
+    rebuses: &HashMap<char, &str>,
+) -> Result<(u8, u8, Vec<CrosswordCell>, Vec<CellMarkup>), XdParseError> {
+    let width = rows[0].chars().count();
+    let height = rows.len();
+    let (Ok(w), Ok(h)) = (u8::try_from(width), u8::try_from(height)) else {
+        return Err(XdParseError::InvalidSize { width, height });
+    };
+    let mut grid = Vec::new();
+    let mut markup = Vec::new();
+    for (row, line) in rows.iter().enumerate() {
+        let actual = line.chars().count();
+        if actual != width {
+            return Err(XdParseError::InvalidWidth { row: row + 1, width, actual });
+        }
+        for c in line.chars() {
+            let cell = match c {
+                '#' | '_' => CrosswordCell::Wall,
+                '.' => CrosswordCell::Empty,
+                c => match rebuses.get(&c) {
+                    Some(rebus) => {
+                        let mut chars = rebus.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) => CrosswordCell::Char(c),
+                            _ => CrosswordCell::Rebus(rebus.to_string()),
+                        }
+                    }
+                    None => CrosswordCell::Char(c.to_ascii_uppercase()),
+                },
+            };
+            grid.push(cell);
+            let marked = special && c.is_ascii_lowercase();
+            markup.push(CellMarkup {
+                circled: marked && !shaded,
+                shaded: marked && shaded,
+                ..Default::default()
+            });
+        }
+    }
+    Ok((w, h, grid, markup))
+}
+
+/// Direction, number, and clue text of a clue line, eg "A1. Clue ~ ANSWER".
+fn parse_clue(line: &str) -> Option<(char, u16, &str)> {
+    let direction = line.chars().next().filter(|c| matches!(c, 'A' | 'D'))?;
+    let (number, rest) = line[1..].split_once('.')?;
+    let number = number.parse().ok()?;
+    let rest = rest.strip_prefix(' ').unwrap_or(rest);
+    let clue = rest.rsplit_once(" ~ ").map_or(rest, |(clue, _answer)| clue);
+    Some((direction, number, clue.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        // fill which could be mistaken for a rebus key, a block, or an empty cell.
+        xword.grid[0] = CrosswordCell::Char('1');
+        xword.grid[2] = CrosswordCell::Char('.');
+        xword.grid[3] = CrosswordCell::Empty;
+        xword.editor = "Ed".to_string();
+        xword.publisher = "Pub".to_string();
+        xword.date = "2024-01-31".to_string();
+        xword.notes = "line one\n\nline three".to_string();
+        let xd = xword.to_xd().unwrap();
+        let round_trip = Crossword::from_xd(&xd).unwrap();
+        assert_eq!(round_trip.to_xd().unwrap(), xd);
+        assert!(matches!(round_trip.grid[0], CrosswordCell::Char('1')));
+        assert!(matches!(&round_trip.grid[1], CrosswordCell::Rebus(s) if s == "TO"));
+        assert!(matches!(round_trip.grid[2], CrosswordCell::Char('.')));
+        assert!(matches!(round_trip.grid[3], CrosswordCell::Empty));
+        assert_eq!((round_trip.editor, round_trip.publisher, round_trip.date), (xword.editor, xword.publisher, xword.date));
+        assert_eq!(round_trip.down_clues, xword.down_clues);
+        assert_eq!(round_trip.notes, xword.notes);
+    }
+
+    #[test]
+    fn test_special_cells() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        xword.grid[3] = CrosswordCell::Char('N');
+        xword.markup[3].shaded = true;
+        let xd = String::from_utf8(xword.to_xd().unwrap()).unwrap();
+        assert!(xd.contains("Special: shaded\n\n\n12\n3n\n"), "{xd}");
+
+        // rebus and non-ASCII cells have no lowercase to mark them with.
+        xword.markup[1].shaded = true;
+        assert!(matches!(xword.to_xd(), Err(ValidationError::UnsupportedCell { format: "xd", row: 1, col: 2 })));
+        xword.markup[1].shaded = false;
+        xword.grid[3] = CrosswordCell::Char('Ñ');
+        assert!(matches!(xword.to_xd(), Err(ValidationError::UnsupportedCell { format: "xd", row: 2, col: 2 })));
+    }
+
+    #[test]
+    fn test_parse() {
+        let xd = "\
+Title: Tiny
+Author: Me
+Rebus: 1=ON 2=#
+Special: circle
+
+
+c12
+A#e
+
+
+A1. Feline, sort of ~ CON#
+
+D1. Regarding ~ CA
+D2. Drink ~ #E
+
+
+line one
+
+line three
+";
+        let xword = Crossword::from_xd(xd.as_bytes()).unwrap();
+        assert_eq!(xword.title, "Tiny");
+        assert_eq!(xword.author, "Me");
+        assert!(matches!(&xword.grid[1], CrosswordCell::Rebus(s) if s == "ON"));
+        assert!(matches!(xword.grid[2], CrosswordCell::Char('#')));
+        assert!(xword.grid[4].is_wall());
+        let circled: Vec<_> = xword.markup.iter().map(|m| m.circled).collect();
+        assert_eq!(circled, [true, false, false, false, false, true]);
+        assert_eq!(xword.across_clues, [(1, "Feline, sort of".to_string())]);
+        assert_eq!(xword.down_clues, [(1, "Regarding".to_string()), (2, "Drink".to_string())]);
+        assert_eq!(xword.notes, "line one\n\nline three");
+
+        assert_eq!(String::from_utf8(xword.to_xd().unwrap()).unwrap(), xd);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let errors = Crossword::from_xd(b"Title: Empty\n").unwrap_err().into_error_map();
+        assert!(matches!(errors["grid"], XdParseError::MissingGrid));
+
+        let errors = Crossword::from_xd(b"Title: x\n\nAB\nC\n").unwrap_err().into_error_map();
+        assert!(matches!(errors["grid"], XdParseError::InvalidWidth { row: 2, width: 2, actual: 1 }));
+
+        let xd = "AB\nCD\n\nA1. One ~ AB\nA3 Three\n";
+        let errors = Crossword::from_xd(xd.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(errors["clues"], XdParseError::InvalidClue { line: 5 }));
+
+        let xd = "AB\nCD\n\nA1. One ~ AB\n\nD1. One ~ AC\nD2. Two ~ BD\n";
+        let errors = Crossword::from_xd(xd.as_bytes()).unwrap_err().into_error_map();
+        assert!(matches!(
+            errors["across_clues"],
+            XdParseError::Validation(ValidationError::MismatchedClueCount { expected: 2, actual: 1 }),
+        ));
+    }
+}
@@ -1,15 +1,43 @@
+mod builder;
+mod generate_exolve;
+mod generate_html;
 mod generate_ipuz;
+mod generate_jpz;
+mod generate_pdf;
 mod generate_puz;
+mod generate_svg;
+mod generate_txt;
+mod generate_xd;
 mod multi_error;
+mod scramble;
 mod serde_lit;
+mod transliterate;
 mod validation;
+mod xml;
 
+use crate::generate_puz::Encoder;
 use crate::validation::ClueError;
-use serde::{Deserialize, Deserializer, Serialize};
+pub use crate::builder::CrosswordBuilder;
+pub use crate::generate_exolve::ExolveParseError;
+pub use crate::generate_ipuz::{DeserializeError, IPuzVersion, LabeledCellError, LabeledCellValue, StringOrNum};
+pub use crate::generate_jpz::JpzError;
+pub use crate::generate_pdf::{PageSize, PdfOptions};
+pub use crate::generate_puz::{
+    Checksum, ChecksumMismatch, PuzParseError, find_puz_key, repair_puz_checksums, unlock_puz,
+    verify_puz_checksums,
+};
+pub use crate::generate_svg::SvgOptions;
+pub use crate::generate_txt::TextParseError;
+pub use crate::generate_xd::XdParseError;
+pub use crate::xml::XmlError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::iter::zip;
 use wasm_bindgen::prelude::*;
 
-pub type MultiError = crate::multi_error::MultiError<ValidationError>;
+pub type MultiError<E = ValidationError> = crate::multi_error::MultiError<E>;
 
+/// Cells are located by `row` and `col`, counting from 1.
 #[derive(thiserror::Error, Debug)]
 pub enum ValidationError {
     #[error("expected {expected} clues, found {actual}")]
@@ -22,15 +50,84 @@ pub enum ValidationError {
     ExtraClue(u16),
     #[error("hard limit of 100 unique rebuses (found {0})")]
     TooManyRebuses(usize),
+    #[error("the rebus in the cell at row {row}, column {col} is empty")]
+    EmptyRebus { row: usize, col: usize },
+    #[error("{format} supports at most {limit} unique rebuses (found {actual})")]
+    TooManyRebusesForFormat {
+        format: &'static str,
+        limit: usize,
+        actual: usize,
+    },
     #[error("expected {} grid elements ({width}x{height}), but found {grid_len}", width * height)]
     InvalidGridSize {
         width: u8,
         height: u8,
         grid_len: usize,
     },
+    #[error("expected {expected} markup elements, but found {actual}")]
+    InvalidMarkupSize { expected: usize, actual: usize },
+    #[error("expected {expected} labels, but found {actual}")]
+    InvalidLabelsSize { expected: usize, actual: usize },
+    #[error("expected {expected} clue details, but found {actual}")]
+    InvalidClueDetailsSize { expected: usize, actual: usize },
+    #[error("clue #{number} refers to cell {cell}, which isn't a letter in the grid")]
+    InvalidClueCell { number: u16, cell: usize },
+    #[error("clue #{number} refers to {reference}-{direction:?}, which isn't an entry in the grid")]
+    InvalidClueReference {
+        number: u16,
+        direction: Direction,
+        reference: u16,
+    },
+    #[error("clue refers to unknown label {0:?}")]
+    UnknownLabel(String),
+    #[error("clue refers to label {0:?}, which more than one entry in that direction starts at")]
+    AmbiguousLabel(String),
+    #[error("clues may be keyed by number or by label, but not both")]
+    CluesByNumberAndLabel,
+    #[error("expected {expected} player entries, but found {actual}")]
+    InvalidPlayerEntriesSize { expected: usize, actual: usize },
     #[error("{0}")]
     EncodingError(#[from] EncodingError),
-    #[error("Unrecognized export format. Expected \"puz1.2\", \"puz2.0\", or \"ipuz\"")]
+    #[error("scramble key must be at most four digits (found {0})")]
+    InvalidScrambleKey(u16),
+    #[error("scrambling requires at least 12 letters (found {0})")]
+    TooFewLettersToScramble(usize),
+    #[error("scrambling requires a solution, but the puzzle has none")]
+    NoSolutionToScramble,
+    #[error("scrambling only supports the letters A-Z (found {found:?} at row {row}, column {col})")]
+    UnscramblableCell { row: usize, col: usize, found: char },
+    #[error("{format} can't represent bars between cells")]
+    UnsupportedBars { format: &'static str },
+    #[error("{format} can't represent cell labels other than the usual numbering")]
+    UnsupportedLabels { format: &'static str },
+    #[error("the cell at row {row}, column {col} is marked given, revealed or incorrect, but the puzzle has no solution")]
+    NoSolution { row: usize, col: usize },
+    #[error("{format} can't represent {found:?} in the cell at row {row}, column {col}")]
+    UnsupportedFill {
+        format: &'static str,
+        row: usize,
+        col: usize,
+        found: char,
+    },
+    #[error("{format} needs a letter in every cell of the solution, but the cell at row {row}, column {col} is empty")]
+    PartialSolution {
+        format: &'static str,
+        row: usize,
+        col: usize,
+    },
+    #[error("{format} can't represent an empty clue (found {number}-{direction:?})")]
+    EmptyClue {
+        format: &'static str,
+        number: u16,
+        direction: Direction,
+    },
+    #[error("{format} can't represent the cell at row {row}, column {col}")]
+    UnsupportedCell {
+        format: &'static str,
+        row: usize,
+        col: usize,
+    },
+    #[error("Unrecognized export format. Expected \"puz1.2\", \"puz2.0\", \"ipuz\", \"txt\", \"jpz\", \"xd\", \"svg\", \"pdf\", \"html\", or \"exolve\"")]
     UnrecognizedFileFormat,
 }
 
@@ -50,19 +147,41 @@ impl From<ClueError> for ValidationError {
 impl Serialize for ValidationError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
-        S: serde::Serializer,
+        S: Serializer,
     {
         serializer.serialize_str(self.to_string().as_ref())
     }
 }
 
-#[derive(Debug, thiserror::Error)]
-#[error("encoding error in {field}")]
+#[derive(Debug, Clone, thiserror::Error)]
+#[error(
+    "can't encode {found:?} (U+{:04X}) in {field}, at character {offset}{}",
+    u32::from(*.found),
+    .substitute.as_ref().map(|s| format!(". Try {s:?} instead")).unwrap_or_default(),
+)]
 pub struct EncodingError {
-    field: String,
+    /// The text which couldn't be encoded, eg "title" or "clue 17A".
+    pub field: String,
+    /// The first character which couldn't be, and how many characters into the field it is.
+    pub found: char,
+    pub offset: usize,
+    /// A stand-in for `found` which can be encoded, if there's an obvious one.
+    pub substitute: Option<String>,
+}
+
+/// A character replaced by `Crossword::export_transliterated`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Substitution {
+    /// The text it was in, eg "title" or "clue 17A".
+    pub field: String,
+    /// How many characters into the field it was.
+    pub offset: usize,
+    pub original: char,
+    /// What it was replaced with. Empty if it was dropped.
+    pub replacement: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CrosswordCell {
     Empty,
     Char(char),
@@ -76,6 +195,20 @@ impl CrosswordCell {
     }
 }
 
+impl Serialize for CrosswordCell {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            CrosswordCell::Empty => serializer.serialize_str(""),
+            CrosswordCell::Char(c) => serializer.serialize_char(*c),
+            CrosswordCell::Rebus(s) => serializer.serialize_str(s),
+            CrosswordCell::Wall => serializer.serialize_none(),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for CrosswordCell {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -96,11 +229,120 @@ impl<'de> Deserialize<'de> for CrosswordCell {
     }
 }
 
+/// Markup on a crossword cell, separate from its fill.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CellMarkup {
+    pub circled: bool,
+    pub shaded: bool,
+    /// The solver previously entered an incorrect answer in this cell.
+    pub previously_incorrect: bool,
+    /// The solver revealed the answer for this cell.
+    pub revealed: bool,
+    /// The answer for this cell is filled in for the solver from the start.
+    pub given: bool,
+    /// A thick bar along the right edge of the cell, ending any across entry here.
+    pub bar_right: bool,
+    /// A thick bar along the bottom edge of the cell, ending any down entry here.
+    pub bar_bottom: bool,
+    /// Background color of the cell, as a hex string like `"#ff0000"`.
+    pub color: Option<String>,
+    /// Color of the cell's text, as a hex string like `"#ff0000"`.
+    pub text_color: Option<String>,
+}
+
+impl CellMarkup {
+    fn is_empty(&self) -> bool {
+        self == &CellMarkup::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    Across,
+    Down,
+}
+
+/// A reference from one clue to another, eg "See 12-Down".
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClueReference {
+    pub direction: Direction,
+    pub number: u16,
+}
+
+/// One answer in the grid: where it is, what it is, and what crosses it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub number: u16,
+    /// The label shown for the entry: its first cell's custom label, or else its number.
+    pub label: String,
+    pub direction: Direction,
+    /// The `(row, col)` of each cell, in reading order, or in the order the clue's details give them.
+    pub cells: Vec<(usize, usize)>,
+    /// The cells' fill, rebuses and all, or `None` if any of them is `Empty`.
+    pub answer: Option<String>,
+    /// For each cell, the entry running the other way through it, if any.
+    pub crossings: Vec<Option<ClueReference>>,
+}
+
+/// Information about a clue beyond its text. Only `.ipuz` files keep it.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ClueDetails {
+    /// Lengths of the words in the answer, eg "3,4" or "5-3".
+    pub enumeration: String,
+    /// The entry's cells, as indices into the `grid`, when they aren't simply a run of cells.
+    pub cells: Vec<usize>,
+    pub references: Vec<ClueReference>,
+    /// Hints for the solver, in the order they should be revealed.
+    pub hints: Vec<String>,
+    /// Shown to the solver once the puzzle is solved.
+    pub explanation: String,
+}
+
+impl ClueDetails {
+    fn is_empty(&self) -> bool {
+        self == &ClueDetails::default()
+    }
+}
+
+/// Where the clues go relative to the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CluePlacement {
+    Before,
+    After,
+    /// In the grid's blocks, as in an arrowword.
+    Blocks,
+}
+
+/// A solver's progress through the puzzle, to be resumed later.
+/// Only written to `.puz` files.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PlayerState {
+    /// Either empty, for no entries, or the solver's entry in each cell of the `grid`.
+    /// Entries in walls are ignored.
+    pub entries: Vec<CrosswordCell>,
+    pub elapsed_seconds: u32,
+    pub paused: bool,
+}
+
 #[non_exhaustive]
+#[derive(Debug, Clone, Copy)]
 pub enum FileFormat {
     Puz12,
     Puz20,
     IPuz,
+    AcrossLiteText,
+    Jpz,
+    Xd,
+    Svg,
+    Pdf,
+    Html,
+    Exolve,
 }
 
 impl std::str::FromStr for FileFormat {
@@ -111,6 +353,13 @@ impl std::str::FromStr for FileFormat {
             "puz1.2" => FileFormat::Puz12,
             "puz2.0" => FileFormat::Puz20,
             "ipuz" => FileFormat::IPuz,
+            "txt" => FileFormat::AcrossLiteText,
+            "jpz" => FileFormat::Jpz,
+            "xd" => FileFormat::Xd,
+            "svg" => FileFormat::Svg,
+            "pdf" => FileFormat::Pdf,
+            "html" => FileFormat::Html,
+            "exolve" => FileFormat::Exolve,
             _ => return Err(ValidationError::UnrecognizedFileFormat),
         };
         Ok(fmt)
@@ -118,16 +367,243 @@ impl std::str::FromStr for FileFormat {
 }
 
 /// Validated crossword struct
+#[derive(Debug, Clone)]
 pub struct Crossword {
     width: u8,
     height: u8,
     grid: Vec<CrosswordCell>,
+    /// One per cell of the `grid`.
+    markup: Vec<CellMarkup>,
+    /// Either empty, for the usual numbering, or one per cell of the `grid`.
+    labels: Vec<String>,
+    /// `entries` has one per cell of the `grid`.
+    player: Option<PlayerState>,
     across_clues: Vec<(u16, String)>,
     down_clues: Vec<(u16, String)>,
+    /// Either empty, or one per clue in `across_clues`.
+    across_details: Vec<ClueDetails>,
+    /// Either empty, or one per clue in `down_clues`.
+    down_details: Vec<ClueDetails>,
     title: String,
     author: String,
     copyright: String,
     notes: String,
+    /// Publication date, in whatever form the publisher uses.
+    date: String,
+    editor: String,
+    publisher: String,
+    difficulty: String,
+    /// Shown to the solver before they start.
+    intro: String,
+    /// Shown to the solver once the puzzle is solved.
+    explanation: String,
+    url: String,
+    /// Where the puzzle came from, eg the program which made it.
+    origin: String,
+    clue_placement: Option<CluePlacement>,
+    /// Show each clue's enumeration to the solver.
+    show_enumerations: bool,
+    /// Answers to the puzzle as a whole, as for a contest or a meta.
+    answers: Vec<String>,
+}
+
+// Read access to a validated crossword. To change one, convert it back
+// into `CrosswordArgs` or a `CrosswordBuilder`, and validate it again.
+impl Crossword {
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    /// Every cell, row by row.
+    pub fn cells(&self) -> &[CrosswordCell] {
+        &self.grid
+    }
+
+    /// The cell at `row`, `col`, or `None` if that's off the grid.
+    pub fn cell(&self, row: usize, col: usize) -> Option<&CrosswordCell> {
+        self.index(row, col).map(|idx| &self.grid[idx])
+    }
+
+    /// Markup for every cell, row by row.
+    pub fn markup(&self) -> &[CellMarkup] {
+        &self.markup
+    }
+
+    /// Markup for the cell at `row`, `col`, or `None` if that's off the grid.
+    pub fn cell_markup(&self, row: usize, col: usize) -> Option<&CellMarkup> {
+        self.index(row, col).map(|idx| &self.markup[idx])
+    }
+
+    /// Custom labels for every cell, row by row, or empty for the usual numbering.
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn player(&self) -> Option<&PlayerState> {
+        self.player.as_ref()
+    }
+
+    pub fn across_clues(&self) -> &[(u16, String)] {
+        &self.across_clues
+    }
+
+    pub fn down_clues(&self) -> &[(u16, String)] {
+        &self.down_clues
+    }
+
+    /// Either empty, or one per clue in `across_clues`.
+    pub fn across_details(&self) -> &[ClueDetails] {
+        &self.across_details
+    }
+
+    /// Either empty, or one per clue in `down_clues`.
+    pub fn down_details(&self) -> &[ClueDetails] {
+        &self.down_details
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    pub fn copyright(&self) -> &str {
+        &self.copyright
+    }
+
+    pub fn notes(&self) -> &str {
+        &self.notes
+    }
+
+    pub fn date(&self) -> &str {
+        &self.date
+    }
+
+    pub fn editor(&self) -> &str {
+        &self.editor
+    }
+
+    pub fn publisher(&self) -> &str {
+        &self.publisher
+    }
+
+    pub fn difficulty(&self) -> &str {
+        &self.difficulty
+    }
+
+    pub fn intro(&self) -> &str {
+        &self.intro
+    }
+
+    pub fn explanation(&self) -> &str {
+        &self.explanation
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    pub fn clue_placement(&self) -> Option<CluePlacement> {
+        self.clue_placement
+    }
+
+    pub fn show_enumerations(&self) -> bool {
+        self.show_enumerations
+    }
+
+    pub fn answers(&self) -> &[String] {
+        &self.answers
+    }
+
+    /// The label shown for clue `number`: its cell's custom label, or else the number itself.
+    /// `None` if no cell has that number.
+    pub fn clue_label(&self, number: u16) -> Option<String> {
+        self.clue_labels().remove(&number)
+    }
+
+    /// The number of the clue in `direction` whose entry starts at the cell labelled `label`,
+    /// if exactly one does. Without custom labels, this is just the number.
+    pub fn clue_number(&self, direction: Direction, label: &str) -> Option<u16> {
+        let numbered: Vec<_> = self.grid().iter_numbered().collect();
+        match labelled_numbers(&numbered, &self.cell_labels(), direction, label)[..] {
+            [number] => Some(number),
+            _ => None,
+        }
+    }
+
+    /// Every entry: across, then down, each in number order.
+    pub fn entries(&self) -> Vec<Entry> {
+        let width = self.width as usize;
+        let (mut across, mut down) = self.grid().entry_cells();
+        // a clue's own cells, for entries which aren't simply a run of them.
+        for (entries, details) in [(&mut across, &self.across_details), (&mut down, &self.down_details)] {
+            for ((_, cells), details) in zip(entries, details) {
+                if !details.cells.is_empty() {
+                    cells.clone_from(&details.cells);
+                }
+            }
+        }
+        let labels = self.clue_labels();
+        // the number of the entry through each cell, in each direction.
+        let numbers = |entries: &EntryCells| {
+            let mut numbers = vec![None; self.grid.len()];
+            for (number, cells) in entries {
+                for &idx in cells {
+                    numbers[idx] = Some(*number);
+                }
+            }
+            numbers
+        };
+        let (across_numbers, down_numbers) = (numbers(&across), numbers(&down));
+        let entry = |direction, (number, cells): (u16, Vec<usize>)| {
+            let (crossing, crossing_numbers) = match direction {
+                Direction::Across => (Direction::Down, &down_numbers),
+                Direction::Down => (Direction::Across, &across_numbers),
+            };
+            let answer = cells
+                .iter()
+                .map(|&idx| match &self.grid[idx] {
+                    CrosswordCell::Char(c) => Some(c.to_string()),
+                    CrosswordCell::Rebus(s) => Some(s.clone()),
+                    CrosswordCell::Empty | CrosswordCell::Wall => None,
+                })
+                .collect();
+            Entry {
+                number,
+                label: labels[&number].clone(),
+                direction,
+                answer,
+                crossings: cells
+                    .iter()
+                    .map(|&idx| crossing_numbers[idx].map(|number| ClueReference { direction: crossing, number }))
+                    .collect(),
+                cells: cells.iter().map(|&idx| (idx / width, idx % width)).collect(),
+            }
+        };
+        let across = across.into_iter().map(|cells| entry(Direction::Across, cells));
+        let down = down.into_iter().map(|cells| entry(Direction::Down, cells));
+        across.chain(down).collect()
+    }
+
+    /// The entry numbered `number` in `direction`, if there is one.
+    pub fn entry(&self, direction: Direction, number: u16) -> Option<Entry> {
+        self.entries().into_iter().find(|entry| entry.direction == direction && entry.number == number)
+    }
+
+    fn index(&self, row: usize, col: usize) -> Option<usize> {
+        let width = self.width as usize;
+        (row < self.height as usize && col < width).then_some(row * width + col)
+    }
 }
 
 impl Crossword {
@@ -136,16 +612,134 @@ impl Crossword {
             width: self.width,
             height: self.height,
             grid: &self.grid,
+            markup: &self.markup,
+        }
+    }
+
+    /// For formats whose numbering only knows about walls.
+    fn check_no_bars(&self, format: &'static str) -> Result<(), ValidationError> {
+        if self.markup.iter().any(|m| m.bar_right || m.bar_bottom) {
+            return Err(ValidationError::UnsupportedBars { format });
+        }
+        Ok(())
+    }
+
+    /// For formats which only know the usual numbering.
+    fn check_no_labels(&self, format: &'static str) -> Result<(), ValidationError> {
+        if !self.labels.is_empty() {
+            return Err(ValidationError::UnsupportedLabels { format });
+        }
+        Ok(())
+    }
+
+    /// Rebuses in the grid and the solver's entries, for writers which assume they were validated.
+    pub(crate) fn check_rebuses(&self) -> Result<(), ValidationError> {
+        self.grid().check_rebuses()?;
+        if let Some(player) = &self.player {
+            check_no_empty_rebus(&player.entries, self.width)?;
+        }
+        Ok(())
+    }
+
+    /// For formats with no way to mark a cell's letter as unknown.
+    fn check_no_empty_cells(&self, format: &'static str) -> Result<(), ValidationError> {
+        let width = self.width as usize;
+        if let Some(idx) = self.grid.iter().position(|cell| matches!(cell, CrosswordCell::Empty)) {
+            let (row, col) = position(idx, width);
+            return Err(ValidationError::PartialSolution { format, row, col });
         }
+        Ok(())
     }
 
-    pub fn export(&self, format: FileFormat) -> Result<Vec<u8>, EncodingError> {
+    /// The label shown in each cell: its custom label, or else its number, if any.
+    fn cell_labels(&self) -> Vec<String> {
+        if self.labels.is_empty() {
+            return self.grid().labels();
+        }
+        self.labels.clone()
+    }
+
+    /// The label shown for each clue number.
+    fn clue_labels(&self) -> HashMap<u16, String> {
+        zip(self.grid().iter_numbered(), self.cell_labels())
+            .filter_map(|(cell, label)| match cell {
+                NumberedCell::Numbered { number, .. } => Some((number, label)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Every text field which `format` can't encode. `export` only reports the first.
+    pub fn check_encoding(&self, format: FileFormat) -> Result<(), MultiError<EncodingError>> {
+        let mut encoder = Encoder::default();
+        // any other problem is for `export` to report.
+        let _ = match format {
+            FileFormat::Puz12 => self.write_puz(*b"1.2\0", &ExportOptions::default(), &mut encoder),
+            FileFormat::Puz20 => self.write_puz(*b"2.0\0", &ExportOptions::default(), &mut encoder),
+            FileFormat::AcrossLiteText => self.write_across_lite_text(&mut encoder),
+            _ => Ok(Vec::new()),
+        };
+        encoder.finish_all()
+    }
+
+    /// A puzzle with no solution, like a contest puzzle whose solution is withheld,
+    /// has no letters in its grid at all: only `Empty` cells and walls.
+    pub fn has_solution(&self) -> bool {
+        self.grid().has_solution()
+    }
+
+    pub fn export(&self, format: FileFormat) -> Result<Vec<u8>, ValidationError> {
+        self.export_with(format, &ExportOptions::default())
+    }
+
+    pub fn export_with(&self, format: FileFormat, options: &ExportOptions) -> Result<Vec<u8>, ValidationError> {
         match format {
-            FileFormat::Puz12 => self.to_puz(*b"1.2\0"),
-            FileFormat::Puz20 => self.to_puz(*b"2.0\0"),
-            FileFormat::IPuz => Ok(self.to_ipuz()),
+            FileFormat::Puz12 => self.to_puz(*b"1.2\0", options),
+            FileFormat::Puz20 => self.to_puz(*b"2.0\0", options),
+            FileFormat::IPuz => Ok(self.to_ipuz_version(options.ipuz_version)),
+            FileFormat::AcrossLiteText => self.to_across_lite_text(),
+            FileFormat::Jpz => Ok(self.to_jpz()),
+            FileFormat::Xd => self.to_xd(),
+            FileFormat::Svg => Ok(self.to_svg(&options.svg).into_bytes()),
+            FileFormat::Pdf => Ok(self.to_pdf(&options.pdf)),
+            FileFormat::Html => Ok(self.to_html()),
+            FileFormat::Exolve => self.to_exolve(),
         }
     }
+
+    /// Export, replacing text `format` can't encode rather than refusing it:
+    /// typographic punctuation with plain, accented letters without their accents,
+    /// and some emoji with a description. Anything else is dropped.
+    /// Returns every replacement made, alongside the file.
+    pub fn export_transliterated(
+        &self,
+        format: FileFormat,
+        options: &ExportOptions,
+    ) -> Result<(Vec<u8>, Vec<Substitution>), ValidationError> {
+        let mut encoder = Encoder::transliterating();
+        let out = match format {
+            FileFormat::Puz12 => self.write_puz(*b"1.2\0", options, &mut encoder)?,
+            FileFormat::Puz20 => self.write_puz(*b"2.0\0", options, &mut encoder)?,
+            FileFormat::AcrossLiteText => self.write_across_lite_text(&mut encoder)?,
+            _ => self.export_with(format, options)?,
+        };
+        Ok((out, encoder.substitutions))
+    }
+}
+
+/// Format-specific options for `Crossword::export_with`.
+/// Options which don't apply to the chosen format are ignored.
+#[derive(Debug, Default, Clone)]
+pub struct ExportOptions {
+    /// Lock the `.puz` solution, scrambling it with this 4-digit key.
+    pub scramble_key: Option<u16>,
+    /// Uppercase `.puz` fill and strip its accents, rather than refusing it.
+    /// Fill which still can't be represented, like "Ω", is refused regardless.
+    pub normalize_fill: bool,
+    /// The version of the ipuz spec to write. v1 leaves out the fields v2 added.
+    pub ipuz_version: IPuzVersion,
+    pub svg: SvgOptions,
+    pub pdf: PdfOptions,
 }
 
 #[wasm_bindgen]
@@ -156,24 +750,77 @@ pub fn generate_puz(blob: JsValue, file_format: String) -> Result<Vec<u8>, Multi
     let xword: CrosswordArgs =
         serde_wasm_bindgen::from_value(blob).expect("js object should be well-formed");
     let xword = xword.validate()?;
-    let puz = xword.export(format).map_err(|err| {
-        MultiError::from([("encoding", err.into())])
-    })?;
-    Ok(puz)
+    // every field which can't be encoded, alongside any other problem.
+    let mut issues = match xword.check_encoding(format) {
+        Ok(()) => MultiError::new(),
+        Err(errs) => errs.map(ValidationError::from),
+    };
+    match xword.export(format) {
+        Ok(puz) if issues.is_empty() => return Ok(puz),
+        Ok(_) | Err(ValidationError::EncodingError(_)) => (),
+        Err(err) => issues.insert("grid", err),
+    }
+    Err(issues)
+}
+
+/// Parse the contents of an `.ipuz` file into a js object of `CrosswordArgs`.
+#[wasm_bindgen]
+pub fn parse_ipuz(ipuz: &[u8]) -> Result<JsValue, MultiError<DeserializeError>> {
+    let xword = Crossword::from_ipuz(ipuz)?;
+    let args = CrosswordArgs::from(xword);
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    Ok(args.serialize(&serializer).expect("crossword should be serializable"))
+}
+
+/// Every entry in a js object of `CrosswordArgs`, with its cells, answer and crossings,
+/// so a player can highlight words without working out the numbering itself.
+#[wasm_bindgen]
+pub fn puzzle_entries(blob: JsValue) -> Result<JsValue, MultiError> {
+    let xword: CrosswordArgs =
+        serde_wasm_bindgen::from_value(blob).expect("js object should be well-formed");
+    let xword = xword.validate()?;
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    Ok(xword.entries().serialize(&serializer).expect("entries should be serializable"))
 }
 
 // ===
 
 /// Simple data struct for the crossword object.
-/// Can be converted into a `Crossword`.
-#[derive(Deserialize)]
+/// Can be converted into a `Crossword`, and back again for editing.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CrosswordArgs {
     pub width: u8,
     pub height: u8,
     pub grid: Vec<CrosswordCell>,
+    /// Either empty, for no markup, or one element per cell of the `grid`.
+    #[serde(default)]
+    pub markup: Vec<CellMarkup>,
+    /// Either empty, for the usual numbering, or the label shown in each cell of the `grid`,
+    /// where an empty string is no label. Clues are keyed by the usual numbering,
+    /// or by label in `across_clues_by_label` and `down_clues_by_label`:
+    /// `Crossword::clue_label` and `Crossword::clue_number` convert between the two.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub player: Option<PlayerState>,
+    #[serde(default)]
     pub across_clues: Vec<(u16, String)>,
+    #[serde(default)]
     pub down_clues: Vec<(u16, String)>,
+    /// Clues keyed by the label of their entry's first cell, in place of `across_clues`.
+    /// `validate` numbers them, so the `Crossword` has them in `across_clues`.
+    #[serde(default)]
+    pub across_clues_by_label: Vec<(String, String)>,
+    /// Clues keyed by the label of their entry's first cell, in place of `down_clues`.
+    #[serde(default)]
+    pub down_clues_by_label: Vec<(String, String)>,
+    /// Either empty, for no details, or one element per clue in `across_clues`.
+    #[serde(default)]
+    pub across_details: Vec<ClueDetails>,
+    /// Either empty, for no details, or one element per clue in `down_clues`.
+    #[serde(default)]
+    pub down_details: Vec<ClueDetails>,
     #[serde(default)]
     pub title: String,
     #[serde(default)]
@@ -182,10 +829,32 @@ pub struct CrosswordArgs {
     pub copyright: String,
     #[serde(default)]
     pub notes: String,
+    #[serde(default)]
+    pub date: String,
+    #[serde(default)]
+    pub editor: String,
+    #[serde(default)]
+    pub publisher: String,
+    #[serde(default)]
+    pub difficulty: String,
+    #[serde(default)]
+    pub intro: String,
+    #[serde(default)]
+    pub explanation: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub origin: String,
+    #[serde(default)]
+    pub clue_placement: Option<CluePlacement>,
+    #[serde(default)]
+    pub show_enumerations: bool,
+    #[serde(default)]
+    pub answers: Vec<String>,
 }
 
 impl CrosswordArgs {
-    pub fn validate(self) -> Result<Crossword, MultiError> {
+    pub fn validate(mut self) -> Result<Crossword, MultiError> {
         let mut issues = MultiError::new();
 
         let expected_len = self.width as usize * self.height as usize;
@@ -203,70 +872,315 @@ impl CrosswordArgs {
         if let Err(err) = self.validate_rebuses() {
             issues.insert("grid", err);
         }
+        if let Err(err) = self.validate_markup() {
+            issues.insert("markup", err);
+        }
+        if let Err(err) = self.validate_labels() {
+            issues.insert("labels", err);
+        }
+        if let Err(err) = self.validate_player() {
+            issues.insert("player", err);
+        }
 
-        let (across, down) = self.grid().expected_grid_nums();
-        if let Err(err) = validation::validate_clues(&across, &self.across_clues) {
-            issues.insert("across_clues", err.into());
+        let across_by_label = self.number_clues_by_label(Direction::Across);
+        let down_by_label = self.number_clues_by_label(Direction::Down);
+        let (across, down) = self.validate_clue_numbers();
+        if let Err(err) = across_by_label.and(across) {
+            issues.insert("across_clues", err);
+        }
+        if let Err(err) = down_by_label.and(down) {
+            issues.insert("down_clues", err);
         }
-        if let Err(err) = validation::validate_clues(&down, &self.down_clues) {
-            issues.insert("down_clues", err.into());
+        for (key, clues, details) in [
+            ("across_details", &self.across_clues, &self.across_details),
+            ("down_details", &self.down_clues, &self.down_details),
+        ] {
+            if let Err(err) = self.validate_clue_details(clues, details) {
+                issues.insert(key, err);
+            }
         }
 
         if !issues.is_empty() {
             return Err(issues);
         }
+        Ok(self.into_crossword())
+    }
 
+    /// Convert to a `Crossword` once `validate` has checked it.
+    fn into_crossword(self) -> Crossword {
+        let expected_len = self.grid.len();
         let CrosswordArgs {
             width,
             height,
             grid,
+            mut markup,
+            mut labels,
+            mut player,
             across_clues,
             down_clues,
+            across_clues_by_label: _,
+            down_clues_by_label: _,
+            mut across_details,
+            mut down_details,
             title,
             author,
             copyright,
             notes,
+            date,
+            editor,
+            publisher,
+            difficulty,
+            intro,
+            explanation,
+            url,
+            origin,
+            clue_placement,
+            show_enumerations,
+            answers,
         } = self;
-        let xword = Crossword {
+        // labels are only worth keeping if they aren't the usual numbering.
+        let usual = Grid { width, height, grid: &grid, markup: &markup }.labels();
+        if labels == usual {
+            labels.clear();
+        }
+        if markup.is_empty() {
+            markup = vec![CellMarkup::default(); expected_len];
+        }
+        for details in [&mut across_details, &mut down_details] {
+            if details.iter().all(ClueDetails::is_empty) {
+                details.clear();
+            }
+        }
+        if let Some(player) = &mut player
+            && player.entries.is_empty()
+        {
+            player.entries = vec![CrosswordCell::Empty; expected_len];
+        }
+        Crossword {
             width,
             height,
             grid,
+            markup,
+            labels,
+            player,
             across_clues,
             down_clues,
+            across_details,
+            down_details,
             title,
             author,
             copyright,
             notes,
-        };
-        Ok(xword)
+            date,
+            editor,
+            publisher,
+            difficulty,
+            intro,
+            explanation,
+            url,
+            origin,
+            clue_placement,
+            show_enumerations,
+            answers,
+        }
     }
 
-    fn validate_rebuses(&self) -> Result<(), ValidationError> {
-        let mut seen_rebus = std::collections::HashSet::new();
+    fn validate_markup(&self) -> Result<(), ValidationError> {
+        let expected = self.grid.len();
+        if !self.markup.is_empty() && self.markup.len() != expected {
+            return Err(ValidationError::InvalidMarkupSize { expected, actual: self.markup.len() });
+        }
+        // without a solution, nothing can be given away, or checked.
+        if !self.grid().has_solution() {
+            let checked = self.markup.iter().position(|m| m.given || m.revealed || m.previously_incorrect);
+            if let Some(idx) = checked {
+                let width = self.width as usize;
+                let (row, col) = position(idx, width);
+                return Err(ValidationError::NoSolution { row, col });
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_labels(&self) -> Result<(), ValidationError> {
+        let expected = self.grid.len();
+        if !self.labels.is_empty() && self.labels.len() != expected {
+            return Err(ValidationError::InvalidLabelsSize { expected, actual: self.labels.len() });
+        }
+        Ok(())
+    }
 
-        for cell in &self.grid {
-            if let CrosswordCell::Rebus(s) = cell {
-                seen_rebus.insert(s);
+    fn validate_player(&self) -> Result<(), ValidationError> {
+        let expected = self.grid.len();
+        if let Some(player) = &self.player {
+            let actual = player.entries.len();
+            if actual != 0 && actual != expected {
+                return Err(ValidationError::InvalidPlayerEntriesSize { expected, actual });
             }
+            check_no_empty_rebus(&player.entries, self.width)?;
         }
+        Ok(())
+    }
 
-        let rebus_count = seen_rebus.len();
-        if rebus_count >= 100 {
-            return Err(ValidationError::TooManyRebuses(rebus_count));
+    /// Move the clues keyed by label in `direction` to the clues keyed by number.
+    fn number_clues_by_label(&mut self, direction: Direction) -> Result<(), ValidationError> {
+        let numbered: Vec<_> = self.grid().iter_numbered().collect();
+        let labels = self.cell_labels();
+        let (by_label, clues, details) = match direction {
+            Direction::Across => (&mut self.across_clues_by_label, &mut self.across_clues, &mut self.across_details),
+            Direction::Down => (&mut self.down_clues_by_label, &mut self.down_clues, &mut self.down_details),
+        };
+        if by_label.is_empty() {
+            return Ok(());
+        }
+        if !clues.is_empty() {
+            return Err(ValidationError::CluesByNumberAndLabel);
+        }
+        let by_number = std::mem::take(by_label)
+            .into_iter()
+            .map(|(label, clue)| match labelled_numbers(&numbered, &labels, direction, &label)[..] {
+                [number] => Ok((number, clue)),
+                [] => Err(ValidationError::UnknownLabel(label)),
+                _ => Err(ValidationError::AmbiguousLabel(label)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // labels needn't be listed in number order, and details go along with their clues.
+        let mut order: Vec<usize> = (0..by_number.len()).collect();
+        order.sort_by_key(|&idx| by_number[idx].0);
+        if details.len() == by_number.len() {
+            *details = order.iter().map(|&idx| details[idx].clone()).collect();
         }
+        *clues = order.iter().map(|&idx| by_number[idx].clone()).collect();
         Ok(())
     }
 
+    /// The label shown in each cell: its custom label, or else its number, if any.
+    fn cell_labels(&self) -> Vec<String> {
+        if self.labels.is_empty() {
+            return self.grid().labels();
+        }
+        self.labels.clone()
+    }
+
+    /// Across and down clues, each checked against the grid's numbering.
+    fn validate_clue_numbers(&self) -> (Result<(), ValidationError>, Result<(), ValidationError>) {
+        let (across, down) = self.grid().expected_grid_nums();
+        (
+            validation::validate_clues(&across, &self.across_clues).map_err(ValidationError::from),
+            validation::validate_clues(&down, &self.down_clues).map_err(ValidationError::from),
+        )
+    }
+
+    fn validate_clue_details(&self, clues: &[(u16, String)], details: &[ClueDetails]) -> Result<(), ValidationError> {
+        if details.is_empty() {
+            return Ok(());
+        }
+        if details.len() != clues.len() {
+            return Err(ValidationError::InvalidClueDetailsSize {
+                expected: clues.len(),
+                actual: details.len(),
+            });
+        }
+        let (across, down) = self.grid().expected_grid_nums();
+        for ((number, _), details) in zip(clues, details) {
+            let invalid = details.cells.iter().find(|&&cell| self.grid.get(cell).is_none_or(CrosswordCell::is_wall));
+            if let Some(&cell) = invalid {
+                return Err(ValidationError::InvalidClueCell { number: *number, cell });
+            }
+            let invalid = details.references.iter().find(|reference| {
+                let entries = if reference.direction == Direction::Across { &across } else { &down };
+                !entries.contains(&reference.number)
+            });
+            if let Some(reference) = invalid {
+                return Err(ValidationError::InvalidClueReference {
+                    number: *number,
+                    direction: reference.direction,
+                    reference: reference.number,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_rebuses(&self) -> Result<(), ValidationError> {
+        self.grid().check_rebuses()
+    }
+
     fn grid(&self) -> Grid<'_> {
         Grid {
             width: self.width,
             height: self.height,
             grid: &self.grid,
+            markup: &self.markup,
+        }
+    }
+}
+
+impl From<Crossword> for CrosswordArgs {
+    fn from(xword: Crossword) -> CrosswordArgs {
+        let Crossword {
+            width,
+            height,
+            grid,
+            mut markup,
+            labels,
+            player,
+            across_clues,
+            down_clues,
+            across_details,
+            down_details,
+            title,
+            author,
+            copyright,
+            notes,
+            date,
+            editor,
+            publisher,
+            difficulty,
+            intro,
+            explanation,
+            url,
+            origin,
+            clue_placement,
+            show_enumerations,
+            answers,
+        } = xword;
+        if markup.iter().all(CellMarkup::is_empty) {
+            markup.clear();
+        }
+        CrosswordArgs {
+            width,
+            height,
+            grid,
+            markup,
+            labels,
+            player,
+            across_clues,
+            down_clues,
+            across_clues_by_label: Vec::new(),
+            down_clues_by_label: Vec::new(),
+            across_details,
+            down_details,
+            title,
+            author,
+            copyright,
+            notes,
+            date,
+            editor,
+            publisher,
+            difficulty,
+            intro,
+            explanation,
+            url,
+            origin,
+            clue_placement,
+            show_enumerations,
+            answers,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum NumberedCell {
     Wall,
     Empty,
@@ -277,27 +1191,92 @@ enum NumberedCell {
     },
 }
 
+impl NumberedCell {
+    /// The number of the entry in `direction` starting at this cell, if any.
+    fn starts(&self, direction: Direction) -> Option<u16> {
+        match (*self, direction) {
+            (NumberedCell::Numbered { number, is_across: true, .. }, Direction::Across) => Some(number),
+            (NumberedCell::Numbered { number, is_down: true, .. }, Direction::Down) => Some(number),
+            _ => None,
+        }
+    }
+}
+
+/// Clue number and the indices of the cells of each entry in one direction.
+type EntryCells = Vec<(u16, Vec<usize>)>;
+
 struct Grid<'xword> {
     width: u8,
     height: u8,
     grid: &'xword [CrosswordCell],
+    /// Either empty, or one per cell of the `grid`. Only the bars matter here.
+    markup: &'xword [CellMarkup],
+}
+
+/// The row and column of the cell at `idx`, counting from 1 as errors do.
+fn position(idx: usize, width: usize) -> (usize, usize) {
+    (idx / width + 1, idx % width + 1)
+}
+
+/// The numbers of the entries in `direction` which start at a cell labelled `label`.
+fn labelled_numbers(numbered: &[NumberedCell], labels: &[String], direction: Direction, label: &str) -> Vec<u16> {
+    zip(numbered, labels)
+        .filter(|(_, cell_label)| *cell_label == label)
+        .filter_map(|(cell, _)| cell.starts(direction))
+        .collect()
+}
+
+/// A rebus with nothing in it can't be written to any format.
+fn check_no_empty_rebus(cells: &[CrosswordCell], width: u8) -> Result<(), ValidationError> {
+    let width = width as usize;
+    if let Some(idx) = cells.iter().position(|cell| matches!(cell, CrosswordCell::Rebus(s) if s.is_empty())) {
+        let (row, col) = position(idx, width);
+        return Err(ValidationError::EmptyRebus { row, col });
+    }
+    Ok(())
 }
 
 impl<'xword> Grid<'xword> {
-    fn iter_numbered(&self) -> impl Iterator<Item = NumberedCell> {
+    /// `.puz` files key their rebuses with two digits, so there can be at most 100 of them.
+    fn check_rebuses(&self) -> Result<(), ValidationError> {
+        check_no_empty_rebus(self.grid, self.width)?;
+        let seen_rebus: std::collections::HashSet<_> = self
+            .grid
+            .iter()
+            .filter_map(|cell| match cell {
+                CrosswordCell::Rebus(s) => Some(s),
+                _ => None,
+            })
+            .collect();
+        let rebus_count = seen_rebus.len();
+        if rebus_count >= 100 {
+            return Err(ValidationError::TooManyRebuses(rebus_count));
+        }
+        Ok(())
+    }
+
+    fn has_solution(&self) -> bool {
+        self.grid.iter().any(|cell| matches!(cell, CrosswordCell::Char(_) | CrosswordCell::Rebus(_)))
+    }
+
+    fn iter_numbered(&self) -> impl Iterator<Item = NumberedCell> + use<'xword> {
         let width = self.width as usize;
         let height = self.height as usize;
+        let grid = self.grid;
+        let markup = self.markup;
+        let bar_right = move |idx: usize| markup.get(idx).is_some_and(|m| m.bar_right);
+        let bar_bottom = move |idx: usize| markup.get(idx).is_some_and(|m| m.bar_bottom);
         let mut number = 1;
-        self.grid.iter().enumerate().map(move |(idx, cell)| {
+        grid.iter().enumerate().map(move |(idx, cell)| {
             if cell.is_wall() {
                 return NumberedCell::Wall;
             }
             let x = idx % width;
             let y = idx / width;
-            let left_wall = x == 0 || self.grid[idx - 1].is_wall();
-            let right_wall = x + 1 == width || self.grid[idx + 1].is_wall();
-            let up_wall = y == 0 || self.grid[idx - width].is_wall();
-            let down_wall = y + 1 == height || self.grid[idx + width].is_wall();
+            let left_wall = x == 0 || grid[idx - 1].is_wall() || bar_right(idx - 1);
+            let right_wall = x + 1 == width || grid[idx + 1].is_wall() || bar_right(idx);
+            let up_wall = y == 0 || grid[idx - width].is_wall() || bar_bottom(idx - width);
+            let down_wall = y + 1 == height || grid[idx + width].is_wall() || bar_bottom(idx);
             // one-long areas do NOT get clues.
             let is_across = left_wall && !right_wall;
             let is_down = up_wall && !down_wall;
@@ -314,6 +1293,16 @@ impl<'xword> Grid<'xword> {
         })
     }
 
+    /// The usual label of each cell: its number, if it has one.
+    fn labels(&self) -> Vec<String> {
+        self.iter_numbered()
+            .map(|cell| match cell {
+                NumberedCell::Numbered { number, .. } => number.to_string(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
     /// Given the shape of the grid, these are the numbers of each clue.
     fn expected_grid_nums(&self) -> (Vec<u16>, Vec<u16>) {
         let mut across = Vec::new();
@@ -330,4 +1319,121 @@ impl<'xword> Grid<'xword> {
         }
         (across, down)
     }
+
+    /// The indices of the cells in each across and down entry, by clue number.
+    fn entry_cells(&self) -> (EntryCells, EntryCells) {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        // an entry runs until a wall, or until a bar across its path.
+        let run = |start: usize, step: usize, len: usize| -> Vec<usize> {
+            let mut cells = Vec::new();
+            for idx in (0..len).map(|i| start + i * step) {
+                if self.grid[idx].is_wall() {
+                    break;
+                }
+                cells.push(idx);
+                let markup = self.markup.get(idx);
+                let barred = if step == 1 { markup.is_some_and(|m| m.bar_right) } else { markup.is_some_and(|m| m.bar_bottom) };
+                if barred {
+                    break;
+                }
+            }
+            cells
+        };
+        let mut across = Vec::new();
+        let mut down = Vec::new();
+        for (idx, cell) in self.iter_numbered().enumerate() {
+            if let NumberedCell::Numbered { number, is_across, is_down } = cell {
+                if is_across {
+                    across.push((number, run(idx, 1, width - idx % width)));
+                }
+                if is_down {
+                    down.push((number, run(idx, width, height - idx / width)));
+                }
+            }
+        }
+        (across, down)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries() {
+        let mut xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        let entries = xword.entries();
+        let numbers: Vec<_> = entries.iter().map(|entry| (entry.direction, entry.number)).collect();
+        assert_eq!(numbers, [(Direction::Across, 1), (Direction::Across, 3), (Direction::Down, 1), (Direction::Down, 2)]);
+
+        // rebuses in full.
+        let down = xword.entry(Direction::Down, 2).unwrap();
+        assert_eq!(down.label, "2");
+        assert_eq!(down.cells, [(0, 1), (1, 1)]);
+        assert_eq!(down.answer.as_deref(), Some("TOON"));
+        let across = |number| Some(ClueReference { direction: Direction::Across, number });
+        assert_eq!(down.crossings, [across(1), across(3)]);
+        assert!(xword.entry(Direction::Across, 2).is_none());
+
+        let json = serde_json::to_value(&down).unwrap();
+        assert_eq!(json["label"], "2");
+        assert_eq!(json["cells"], serde_json::json!([[0, 1], [1, 1]]));
+        assert_eq!(json["crossings"][1], serde_json::json!({ "direction": "across", "number": 3 }));
+
+        xword.grid[3] = CrosswordCell::Empty;
+        assert_eq!(xword.entry(Direction::Down, 2).unwrap().answer, None);
+        assert_eq!(xword.entry(Direction::Down, 1).unwrap().answer.as_deref(), Some("ONLY"));
+    }
+
+    #[test]
+    fn test_entries_labels_and_cells() {
+        let xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        let mut args = CrosswordArgs::from(xword);
+        args.labels = ["A", "B", "C", ""].map(String::from).to_vec();
+        // 1-Across read backwards.
+        args.across_details = vec![ClueDetails { cells: vec![1, 0], ..Default::default() }, ClueDetails::default()];
+        let xword = args.validate().unwrap();
+
+        let labels: Vec<_> = xword.entries().into_iter().map(|entry| entry.label).collect();
+        assert_eq!(labels, ["A", "C", "A", "B"]);
+
+        let across = xword.entry(Direction::Across, 1).unwrap();
+        assert_eq!(across.cells, [(0, 1), (0, 0)]);
+        assert_eq!(across.answer.as_deref(), Some("TOON"));
+        let down = |number| Some(ClueReference { direction: Direction::Down, number });
+        assert_eq!(across.crossings, [down(2), down(1)]);
+    }
+
+    #[test]
+    fn test_clues_by_label() {
+        let xword = Crossword::from_puz(include_bytes!("test_files/smol.puz")).unwrap();
+        let (across_clues, down_clues) = (xword.across_clues.clone(), xword.down_clues.clone());
+        let mut args = CrosswordArgs::from(xword);
+        args.labels = ["A", "B", "C", ""].map(String::from).to_vec();
+        args.across_clues.clear();
+        args.down_clues.clear();
+        let by_label = |clues: &[(&str, &str)]| clues.iter().map(|&(label, clue)| (label.into(), clue.into())).collect();
+        // in any order, with their details alongside.
+        args.across_clues_by_label = by_label(&[("C", "French city"), ("A", "Aware of")]);
+        let enumeration = |enumeration: &str| ClueDetails { enumeration: enumeration.into(), ..Default::default() };
+        args.across_details = vec![enumeration("5"), enumeration("4")];
+        args.down_clues_by_label = by_label(&[("A", "Solely"), ("B", "Animated sort")]);
+        let xword = args.clone().validate().unwrap();
+        assert_eq!(xword.across_clues, across_clues);
+        assert_eq!(xword.down_clues, down_clues);
+        assert_eq!(xword.across_details[0].enumeration, "4");
+
+        let mut bad = args.clone();
+        bad.across_clues_by_label[0].0 = "Z".into();
+        bad.down_clues = down_clues;
+        let errors = bad.validate().unwrap_err().into_error_map();
+        assert!(matches!(&errors["across_clues"], ValidationError::UnknownLabel(label) if label == "Z"));
+        assert!(matches!(errors["down_clues"], ValidationError::CluesByNumberAndLabel));
+
+        args.labels[1] = "A".into();
+        let errors = args.validate().unwrap_err().into_error_map();
+        assert!(matches!(&errors["down_clues"], ValidationError::AmbiguousLabel(label) if label == "A"));
+        assert!(!errors.contains_key("across_clues"));
+    }
 }
@@ -1,9 +1,10 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use wasm_bindgen::JsValue;
 
 #[derive(Debug)]
 pub struct MultiError<E> {
-    errors: HashMap<&'static str, E>,
+    errors: HashMap<Cow<'static, str>, E>,
 }
 
 impl<E> MultiError<E> {
@@ -17,11 +18,19 @@ impl<E> MultiError<E> {
         self.errors.is_empty()
     }
 
-    pub(crate) fn insert(&mut self, section: &'static str, err: E) {
-        self.errors.insert(section, err);
+    /// Sections are usually a field name, but may be made up, eg "clue 17A".
+    pub(crate) fn insert(&mut self, section: impl Into<Cow<'static, str>>, err: E) {
+        self.errors.insert(section.into(), err);
     }
 
-    pub fn into_error_map(self) -> HashMap<&'static str, E> {
+    /// Convert each error, eg from `ValidationError` into a format's own error type.
+    pub(crate) fn map<F>(self, f: impl Fn(E) -> F) -> MultiError<F> {
+        let errors = self.errors.into_iter().map(|(k, v)| (k, f(v))).collect();
+        MultiError { errors }
+    }
+
+    /// Keys are `Cow`s rather than `&'static str`s, since some are made up, eg "clue 17A".
+    pub fn into_error_map(self) -> HashMap<Cow<'static, str>, E> {
         self.errors
     }
 }
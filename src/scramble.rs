@@ -0,0 +1,166 @@
+// Across Lite can "lock" a `.puz` solution by scrambling it with a 4-digit key.
+// Only the letters are scrambled: they're read top to bottom, left to right
+// (column-major!), skipping walls, and then each digit of the key in turn
+// shifts the letters, rotates them, and riffle-shuffles them like a deck of cards.
+//
+// The header stores a checksum of the unscrambled letters so that a
+// solver can tell when they've entered the correct key.
+
+use crate::{ValidationError, position};
+use crate::generate_puz::cksum_region;
+
+/// Value of the header's scrambled tag for a locked puzzle.
+pub(crate) const SCRAMBLED_TAG: u16 = 0x0004;
+
+/// Across Lite refuses to scramble a puzzle with fewer letters than this.
+const MIN_SCRAMBLE_LEN: usize = 12;
+
+fn key_digits(key: u16) -> Option<[u8; 4]> {
+    if key > 9999 {
+        return None;
+    }
+    let key = key as usize;
+    Some([key / 1000, key / 100 % 10, key / 10 % 10, key % 10].map(|d| d as u8))
+}
+
+/// Indices into the solution of every non-wall cell, in column-major order.
+fn letter_indices(solution: &[u8], width: u8, height: u8) -> Vec<usize> {
+    let width = width as usize;
+    let height = height as usize;
+    (0..width)
+        .flat_map(|x| (0..height).map(move |y| y * width + x))
+        .filter(|&idx| !matches!(solution[idx], b'.' | b':'))
+        .collect()
+}
+
+/// Scramble the solution in place, returning the checksum of the unscrambled letters.
+pub(crate) fn scramble_solution(
+    solution: &mut [u8],
+    width: u8,
+    height: u8,
+    key: u16,
+) -> Result<u16, ValidationError> {
+    let key = key_digits(key).ok_or(ValidationError::InvalidScrambleKey(key))?;
+    let indices = letter_indices(solution, width, height);
+    let mut letters: Vec<u8> = indices.iter().map(|&idx| solution[idx]).collect();
+
+    let invalid = indices.iter().zip(&letters).find(|(_, letter)| !letter.is_ascii_uppercase());
+    if let Some((&idx, &letter)) = invalid {
+        let (row, col) = position(idx, width as usize);
+        return Err(ValidationError::UnscramblableCell { row, col, found: letter as char });
+    }
+    if letters.len() < MIN_SCRAMBLE_LEN {
+        return Err(ValidationError::TooFewLettersToScramble(letters.len()));
+    }
+
+    let checksum = cksum_region(&letters, 0);
+    for &digit in &key {
+        shift(&mut letters, key, true);
+        letters.rotate_left(digit as usize);
+        letters = shuffle(&letters);
+    }
+    for (idx, letter) in indices.into_iter().zip(letters) {
+        solution[idx] = letter;
+    }
+    Ok(checksum)
+}
+
+/// Unscramble the solution in place, returning the checksum of the unscrambled letters.
+///
+/// Any key will "successfully" unscramble the solution: compare the checksum against
+/// the header's scrambled checksum to tell if it was the right one.
+/// Returns `None` if the key isn't four digits, or the solution isn't all letters.
+pub(crate) fn unscramble_solution(solution: &mut [u8], width: u8, height: u8, key: u16) -> Option<u16> {
+    let key = key_digits(key)?;
+    let indices = letter_indices(solution, width, height);
+    let mut letters: Vec<u8> = indices.iter().map(|&idx| solution[idx]).collect();
+    if !letters.iter().all(u8::is_ascii_uppercase) {
+        return None;
+    }
+
+    for &digit in key.iter().rev() {
+        letters = unshuffle(&letters);
+        letters.rotate_right(digit as usize);
+        shift(&mut letters, key, false);
+    }
+    let checksum = cksum_region(&letters, 0);
+    for (idx, letter) in indices.into_iter().zip(letters) {
+        solution[idx] = letter;
+    }
+    Some(checksum)
+}
+
+/// Caesar shift each letter by the corresponding digit of the key.
+fn shift(letters: &mut [u8], key: [u8; 4], forward: bool) {
+    for (letter, &digit) in letters.iter_mut().zip(key.iter().cycle()) {
+        let digit = if forward { digit } else { 26 - digit };
+        *letter = (*letter - b'A' + digit) % 26 + b'A';
+    }
+}
+
+/// Interleave the back half of the letters with the front half.
+fn shuffle(letters: &[u8]) -> Vec<u8> {
+    let mid = letters.len() / 2;
+    let (front, back) = letters.split_at(mid);
+    let mut out: Vec<u8> = back.iter().zip(front).flat_map(|(&b, &f)| [b, f]).collect();
+    if letters.len() % 2 == 1 {
+        out.push(letters[letters.len() - 1]);
+    }
+    out
+}
+
+/// Inverse of `shuffle`.
+fn unshuffle(letters: &[u8]) -> Vec<u8> {
+    let odds = letters.iter().skip(1).step_by(2);
+    let evens = letters.iter().step_by(2);
+    odds.chain(evens).copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scramble_solution() {
+        let mut solution = *b"ABCD.EFGHIJKLMN.";
+        let original = solution;
+        let checksum = scramble_solution(&mut solution, 4, 4, 1234).unwrap();
+        assert_ne!(solution, original);
+        // walls stay put.
+        assert_eq!(solution[4], b'.');
+        assert_eq!(solution[15], b'.');
+        assert!(solution.iter().all(|&c| c == b'.' || c.is_ascii_uppercase()));
+
+        // column-major: AHL BEIM CFJN DGK
+        assert_eq!(checksum, cksum_region(b"AHLBEIMCFJNDGK", 0));
+    }
+
+    #[test]
+    fn test_unscramble_solution() {
+        // odd and even numbers of letters
+        for (solution, width, height) in [(&b"ABCD.EFGHIJKLMN."[..], 4, 4), (b"THEQUICKBROWNFOXJUMPS", 3, 7)] {
+            let mut scrambled = solution.to_vec();
+            let checksum = scramble_solution(&mut scrambled, width, height, 7395).unwrap();
+            assert_eq!(unscramble_solution(&mut scrambled, width, height, 7395), Some(checksum));
+            assert_eq!(scrambled, solution);
+        }
+    }
+
+    #[test]
+    fn test_scramble_errors() {
+        let mut solution = *b"ABCD.EFGHIJKLMN.";
+        assert!(matches!(
+            scramble_solution(&mut solution, 4, 4, 10000),
+            Err(ValidationError::InvalidScrambleKey(10000)),
+        ));
+        assert!(matches!(
+            scramble_solution(&mut solution[..9], 3, 3, 1234),
+            Err(ValidationError::TooFewLettersToScramble(8)),
+        ));
+        solution[5] = b'e';
+        assert!(matches!(
+            scramble_solution(&mut solution, 4, 4, 1234),
+            Err(ValidationError::UnscramblableCell { row: 2, col: 2, found: 'e' }),
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+// Near equivalents for characters which the older formats can't represent.
+// `.puz` 1.x and Across Lite text are Windows-1252, and `.puz` fill is only A-Z, 0-9,
+// and a few symbols, so text from anywhere else sometimes needs a stand-in.
+
+/// Accented Latin letters, by the letter without its accent.
+const ACCENTED_LETTERS: [(&str, char); 19] = [
+    ("ÀÁÂÃÄÅàáâãäåĀāĂăĄą", 'A'),
+    ("ÇçĆćĈĉĊċČč", 'C'),
+    ("ĎďĐđ", 'D'),
+    ("ÈÉÊËèéêëĒēĔĕĖėĘęĚě", 'E'),
+    ("ĜĝĞğĠġĢģ", 'G'),
+    ("ĤĥĦħ", 'H'),
+    ("ÌÍÎÏìíîïĨĩĪīĬĭĮįİı", 'I'),
+    ("Ĵĵ", 'J'),
+    ("Ķķ", 'K'),
+    ("ĹĺĻļĽľĿŀŁł", 'L'),
+    ("ÑñŃńŅņŇň", 'N'),
+    ("ÒÓÔÕÖØòóôõöøŌōŎŏŐő", 'O'),
+    ("ŔŕŖŗŘř", 'R'),
+    ("ŚśŜŝŞşŠš", 'S'),
+    ("ŢţŤťŦŧ", 'T'),
+    ("ÙÚÛÜùúûüŨũŪūŬŭŮůŰűŲų", 'U'),
+    ("Ŵŵ", 'W'),
+    ("ÝýÿŶŷŸ", 'Y'),
+    ("ŹźŻżŽž", 'Z'),
+];
+
+/// Typographic punctuation and symbols, by their plain-text spelling.
+const PUNCTUATION: [(char, &str); 27] = [
+    ('\u{2018}', "'"),
+    ('\u{2019}', "'"),
+    ('\u{201A}', ","),
+    ('\u{201B}', "'"),
+    ('\u{201C}', "\""),
+    ('\u{201D}', "\""),
+    ('\u{201E}', "\""),
+    ('\u{201F}', "\""),
+    ('\u{2032}', "'"),
+    ('\u{2033}', "\""),
+    ('\u{2010}', "-"),
+    ('\u{2011}', "-"),
+    ('\u{2012}', "-"),
+    ('\u{2013}', "-"),
+    ('\u{2014}', "--"),
+    ('\u{2015}', "--"),
+    ('\u{2212}', "-"),
+    ('\u{2026}', "..."),
+    ('\u{2044}', "/"),
+    ('\u{2153}', "1/3"),
+    ('\u{2154}', "2/3"),
+    ('\u{2190}', "<-"),
+    ('\u{2192}', "->"),
+    ('\u{2260}', "!="),
+    ('\u{2264}', "<="),
+    ('\u{2265}', ">="),
+    ('\u{2116}', "No."),
+];
+
+/// Common emoji, by what they show.
+const EMOJI: [(char, &str); 20] = [
+    ('\u{2600}', "sun"),
+    ('\u{2601}', "cloud"),
+    ('\u{2615}', "coffee"),
+    ('\u{2764}', "heart"),
+    ('\u{2B50}', "star"),
+    ('\u{1F319}', "moon"),
+    ('\u{1F355}', "pizza"),
+    ('\u{1F389}', "party popper"),
+    ('\u{1F431}', "cat"),
+    ('\u{1F436}', "dog"),
+    ('\u{1F44D}', "thumbs up"),
+    ('\u{1F44E}', "thumbs down"),
+    ('\u{1F525}', "fire"),
+    ('\u{1F600}', "grinning face"),
+    ('\u{1F602}', "face with tears of joy"),
+    ('\u{1F609}', "winking face"),
+    ('\u{1F642}', "smiling face"),
+    ('\u{1F914}', "thinking face"),
+    ('\u{1F9E9}', "puzzle piece"),
+    ('\u{1FADB}', "pea pod"),
+];
+
+/// `c` without its accent, keeping its case. Anything else is left alone.
+pub(crate) fn strip_accent(c: char) -> char {
+    match ACCENTED_LETTERS.iter().find(|(accented, _)| accented.contains(c)) {
+        Some(&(_, letter)) if c.is_lowercase() => letter.to_ascii_lowercase(),
+        Some(&(_, letter)) => letter,
+        None => c,
+    }
+}
+
+/// A plain stand-in for `c`, if there's an obvious one.
+/// Every stand-in is ASCII, so it fits any encoding we write.
+pub(crate) fn substitute(c: char) -> Option<String> {
+    if let Some(&(_, plain)) = PUNCTUATION.iter().find(|&&(typographic, _)| typographic == c) {
+        return Some(plain.to_owned());
+    }
+    if c.is_whitespace() {
+        return Some(" ".to_owned());
+    }
+    let letter = strip_accent(c);
+    (letter != c).then(|| letter.to_string())
+}
+
+/// What an emoji shows, for when there's no stand-in for it but words.
+pub(crate) fn describe(c: char) -> Option<&'static str> {
+    EMOJI.iter().find(|&&(emoji, _)| emoji == c).map(|&(_, description)| description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitute() {
+        assert_eq!(strip_accent('é'), 'e');
+        assert_eq!(strip_accent('Ő'), 'O');
+        assert_eq!(strip_accent('Ω'), 'Ω');
+        assert_eq!(substitute('’').as_deref(), Some("'"));
+        assert_eq!(substitute('ā').as_deref(), Some("a"));
+        assert_eq!(substitute('\u{2009}').as_deref(), Some(" "));
+        assert_eq!(substitute('🫛'), None);
+        assert_eq!(describe('🫛'), Some("pea pod"));
+    }
+}
@@ -0,0 +1,310 @@
+// Just enough XML for the crossword formats that use it.
+// No DTDs, no namespaces beyond ignoring their prefixes, no validation.
+
+use std::fmt::Write;
+
+/// Elements are parsed recursively, so nesting deeper than this is refused
+/// rather than overflowing the stack.
+const MAX_DEPTH: usize = 256;
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid xml at byte {offset}: {message}")]
+pub struct XmlError {
+    offset: usize,
+    message: &'static str,
+}
+
+#[derive(Debug)]
+pub(crate) enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| local_name(key) == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Child elements with the given name, ignoring any namespace prefix.
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter_map(move |node| match node {
+            Node::Element(elem) if local_name(&elem.name) == name => Some(elem),
+            _ => None,
+        })
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find_map(|node| match node {
+            Node::Element(elem) if local_name(&elem.name) == name => Some(elem),
+            _ => None,
+        })
+    }
+
+    /// All the text within this element, with any markup stripped.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        for node in &self.children {
+            match node {
+                Node::Element(elem) => out.push_str(&elem.text()),
+                Node::Text(text) => out.push_str(text),
+            }
+        }
+        out
+    }
+}
+
+/// Escape text for use in an XML (or HTML) attribute value or text node.
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else { break };
+        let decoded = match &rest[1..end] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            entity => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            // not an entity we know: leave it be.
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    /// How many elements we're inside of.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn error(&self, message: &'static str) -> XmlError {
+        XmlError { offset: self.pos, message }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Advance past the next occurrence of `pat`, returning the text before it.
+    fn take_until(&mut self, pat: &str) -> Result<&'a str, XmlError> {
+        let rest = self.rest();
+        let idx = rest.find(pat).ok_or_else(|| self.error("unexpected end of file"))?;
+        self.pos += idx + pat.len();
+        Ok(&rest[..idx])
+    }
+
+    fn take_name(&mut self) -> Result<&'a str, XmlError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn expect(&mut self, pat: &str) -> Result<(), XmlError> {
+        if !self.rest().starts_with(pat) {
+            return Err(self.error("unexpected character"));
+        }
+        self.pos += pat.len();
+        Ok(())
+    }
+
+    /// Skip a comment, processing instruction, or doctype, if there is one here.
+    fn skip_misc(&mut self) -> Result<bool, XmlError> {
+        let rest = self.rest();
+        if rest.starts_with("<!--") {
+            self.take_until("-->")?;
+        } else if rest.starts_with("<?") {
+            self.take_until("?>")?;
+        } else if rest.starts_with("<!") && !rest.starts_with("<![CDATA[") {
+            self.take_until(">")?;
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn parse_element(&mut self) -> Result<Element, XmlError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("elements nested too deeply"));
+        }
+        self.depth += 1;
+        let element = self.parse_element_inner();
+        self.depth -= 1;
+        element
+    }
+
+    fn parse_element_inner(&mut self) -> Result<Element, XmlError> {
+        self.expect("<")?;
+        let name = self.take_name()?.to_owned();
+        let mut attrs = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(Element { name, attrs, children: Vec::new() });
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let key = self.take_name()?.to_owned();
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let quote = if self.rest().starts_with('\'') { "'" } else { "\"" };
+            self.expect(quote)?;
+            let value = unescape(self.take_until(quote)?);
+            attrs.push((key, value));
+        }
+
+        let mut children = Vec::new();
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(self.error("unexpected end of file"));
+            } else if rest.starts_with("</") {
+                self.pos += 2;
+                if self.take_until(">")?.trim() != name {
+                    return Err(self.error("mismatched closing tag"));
+                }
+                return Ok(Element { name, attrs, children });
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += "<![CDATA[".len();
+                children.push(Node::Text(self.take_until("]]>")?.to_owned()));
+            } else if self.skip_misc()? {
+                continue;
+            } else if rest.starts_with('<') {
+                children.push(Node::Element(self.parse_element()?));
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                self.pos += len;
+                children.push(Node::Text(unescape(&rest[..len])));
+            }
+        }
+    }
+}
+
+/// Parse an XML document, returning its root element.
+pub(crate) fn parse(src: &str) -> Result<Element, XmlError> {
+    let mut parser = Parser { src: src.trim_start_matches('\u{feff}'), pos: 0, depth: 0 };
+    loop {
+        parser.skip_whitespace();
+        if !parser.skip_misc()? {
+            break;
+        }
+    }
+    parser.parse_element()
+}
+
+/// Write an element's opening tag, with its attributes.
+pub(crate) fn open_tag(out: &mut String, name: &str, attrs: &[(&str, &str)], self_closing: bool) {
+    write!(out, "<{name}").unwrap();
+    for (key, value) in attrs {
+        write!(out, " {key}=\"{}\"", escape(value)).unwrap();
+    }
+    out.push_str(if self_closing { "/>" } else { ">" });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let doc = r#"<?xml version="1.0"?>
+<!-- comment -->
+<root xmlns:x="urn:x" a='1 &amp; 2'>
+  <x:child b="&#65;&#x42;">one <i>two</i> three</x:child>
+  <empty/>
+  <![CDATA[<raw>]]>
+</root>"#;
+        let root = parse(doc).unwrap();
+        assert_eq!(root.attr("a"), Some("1 & 2"));
+        let child = root.child("child").unwrap();
+        assert_eq!(child.attr("b"), Some("AB"));
+        assert_eq!(child.text(), "one two three");
+        assert!(root.child("empty").is_some());
+        assert!(root.text().contains("<raw>"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse("<a><b></a>").is_err());
+        assert!(parse("<a>").is_err());
+
+        let nested = format!("{}{}", "<a>".repeat(100_000), "</a>".repeat(100_000));
+        let err = parse(&nested).unwrap_err();
+        assert_eq!(err.message, "elements nested too deeply");
+        let nested = format!("{}{}", "<a>".repeat(MAX_DEPTH), "</a>".repeat(MAX_DEPTH));
+        assert!(parse(&nested).is_ok());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r#"<a href="x">&'"#), "&lt;a href=&quot;x&quot;&gt;&amp;&#39;");
+        assert_eq!(unescape(&escape("<&>\"'")), "<&>\"'");
+        assert_eq!(unescape("AT&T &bogus;"), "AT&T &bogus;");
+    }
+}